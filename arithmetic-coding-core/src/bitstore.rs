@@ -1,22 +1,29 @@
-use std::ops::{Add, AddAssign, Div, Mul, Shl, ShlAssign, Sub};
+use core::ops::{Add, AddAssign, BitAnd, Div, Mul, Shl, ShlAssign, Shr, Sub};
 
 /// A trait for a type that can be used for the internal integer representation
 /// of an encoder or decoder
 pub trait BitStore:
     Shl<u32, Output = Self>
     + ShlAssign<u32>
+    + Shr<u32, Output = Self>
     + Sub<Output = Self>
     + Add<Output = Self>
     + Mul<Output = Self>
     + Div<Output = Self>
+    + BitAnd<Output = Self>
     + AddAssign
     + PartialOrd
     + Copy
-    + std::fmt::Debug
+    + From<u8>
+    + core::fmt::Debug
 {
     /// the number of bits needed to represent this type
     const BITS: u32;
 
+    /// the number of bits available to the intermediate product in
+    /// [`BitStore::mul_div`]
+    const WIDE_BITS: u32;
+
     /// the additive identity
     const ZERO: Self;
 
@@ -25,23 +32,192 @@ pub trait BitStore:
 
     /// integer natural logarithm, rounded down
     fn log2(self) -> u32;
+
+    /// the least significant byte of this value (the remaining bits are
+    /// truncated)
+    fn low_byte(self) -> u8;
+
+    /// convert to a floating point number (rounding to the nearest
+    /// representable value)
+    fn to_f64(self) -> f64;
+
+    /// `self + other`, or `None` if the sum overflows
+    fn checked_add(self, other: Self) -> Option<Self>;
+
+    /// `self * other`, or `None` if the product overflows
+    fn checked_mul(self, other: Self) -> Option<Self>;
+
+    /// `self * numerator / denominator`, rounded down.
+    ///
+    /// The product is calculated in a wider type (where there is one), so it
+    /// can use up to [`BitStore::WIDE_BITS`] bits without overflowing. The
+    /// result must fit in `Self`.
+    #[must_use]
+    fn mul_div(self, numerator: Self, denominator: Self) -> Self;
+
+    /// `self * numerator / denominator`, rounded up.
+    ///
+    /// See [`BitStore::mul_div`].
+    #[must_use]
+    fn mul_div_ceil(self, numerator: Self, denominator: Self) -> Self;
+
+    /// `self * numerator / 2^shift`, rounded down.
+    ///
+    /// This is equivalent to [`BitStore::mul_div`] with a power-of-two
+    /// denominator, but doesn't require a division.
+    #[must_use]
+    fn mul_shr(self, numerator: Self, shift: u32) -> Self;
+
+    /// Precompute the reciprocal of `self`, for dividing by `self` without a
+    /// division instruction. See [`BitStore::mul_div_reciprocal`].
+    ///
+    /// `self` must not be zero.
+    fn reciprocal(self) -> Reciprocal<Self>;
+
+    /// `self * numerator / divisor`, rounded down, where `reciprocal` is the
+    /// [reciprocal](BitStore::reciprocal) of `divisor`.
+    ///
+    /// This gives the same result as [`BitStore::mul_div`], using
+    /// multiplications in place of the division.
+    #[must_use]
+    fn mul_div_reciprocal(self, numerator: Self, reciprocal: Reciprocal<Self>) -> Self;
+}
+
+/// The precomputed reciprocal of a divisor. See [`BitStore::reciprocal`].
+///
+/// This uses the method from Möller and Granlund, 'Improved division by
+/// invariant integers' (2011).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Reciprocal<B> {
+    divisor: B,
+    shift: u32,
+    inverse: B,
+}
+
+impl<B: Copy> Reciprocal<B> {
+    /// The divisor
+    pub const fn divisor(&self) -> B {
+        self.divisor
+    }
 }
 
 macro_rules! impl_bitstore {
+    ($t:ty, $wide:ty) => {
+        impl BitStore for $t {
+            impl_bitstore!(@common $t, $wide);
+
+            #[allow(clippy::cast_possible_truncation, clippy::cast_lossless)]
+            fn mul_shr(self, numerator: Self, shift: u32) -> Self {
+                ((self as $wide * numerator as $wide) >> shift) as Self
+            }
+
+            #[allow(clippy::cast_possible_truncation, clippy::cast_lossless)]
+            fn reciprocal(self) -> Reciprocal<Self> {
+                let shift = self.leading_zeros();
+                // the divisor is normalised, so this is between 2^BITS and
+                // 2^(BITS + 1), and the truncation subtracts 2^BITS
+                let inverse = (<$wide>::MAX / (self << shift) as $wide) as Self;
+                Reciprocal {
+                    divisor: self,
+                    shift,
+                    inverse,
+                }
+            }
+
+            #[allow(clippy::cast_possible_truncation, clippy::cast_lossless)]
+            fn mul_div_reciprocal(self, numerator: Self, reciprocal: Reciprocal<Self>) -> Self {
+                let Reciprocal {
+                    divisor,
+                    shift,
+                    inverse,
+                } = reciprocal;
+                let divisor = divisor << shift;
+                let dividend = (self as $wide * numerator as $wide) << shift;
+                let high = (dividend >> Self::BITS) as Self;
+                let low = dividend as Self;
+
+                let estimate = (inverse as $wide * high as $wide).wrapping_add(dividend);
+                let mut quotient = ((estimate >> Self::BITS) as Self).wrapping_add(1);
+                let mut remainder = low.wrapping_sub(quotient.wrapping_mul(divisor));
+                if remainder > estimate as Self {
+                    quotient = quotient.wrapping_sub(1);
+                    remainder = remainder.wrapping_add(divisor);
+                }
+                if remainder >= divisor {
+                    quotient += 1;
+                }
+                quotient
+            }
+        }
+    };
+    // there is no wider type, so the product must fit in `$t`
     ($t:ty) => {
         impl BitStore for $t {
-            const BITS: u32 = Self::BITS;
-            const ONE: Self = 1;
-            const ZERO: Self = 0;
+            impl_bitstore!(@common $t, $t);
+
+            fn mul_shr(self, numerator: Self, shift: u32) -> Self {
+                (self * numerator) >> shift
+            }
+
+            fn reciprocal(self) -> Reciprocal<Self> {
+                Reciprocal {
+                    divisor: self,
+                    shift: 0,
+                    inverse: 0,
+                }
+            }
 
-            fn log2(self) -> u32 {
-                Self::ilog2(self)
+            fn mul_div_reciprocal(self, numerator: Self, reciprocal: Reciprocal<Self>) -> Self {
+                self.mul_div(numerator, reciprocal.divisor)
             }
         }
     };
+    (@common $t:ty, $wide:ty) => {
+        const BITS: u32 = Self::BITS;
+        const ONE: Self = 1;
+        const WIDE_BITS: u32 = <$wide>::BITS;
+        const ZERO: Self = 0;
+
+        fn log2(self) -> u32 {
+            Self::ilog2(self)
+        }
+
+        #[allow(clippy::cast_possible_truncation)]
+        fn low_byte(self) -> u8 {
+            self as u8
+        }
+
+        #[allow(clippy::cast_precision_loss, clippy::cast_lossless)]
+        fn to_f64(self) -> f64 {
+            self as f64
+        }
+
+        fn checked_add(self, other: Self) -> Option<Self> {
+            <$t>::checked_add(self, other)
+        }
+
+        fn checked_mul(self, other: Self) -> Option<Self> {
+            <$t>::checked_mul(self, other)
+        }
+
+        #[allow(clippy::cast_possible_truncation, clippy::cast_lossless)]
+        fn mul_div(self, numerator: Self, denominator: Self) -> Self {
+            (self as $wide * numerator as $wide / denominator as $wide) as Self
+        }
+
+        #[allow(clippy::cast_possible_truncation, clippy::cast_lossless)]
+        fn mul_div_ceil(self, numerator: Self, denominator: Self) -> Self {
+            (self as $wide * numerator as $wide).div_ceil(denominator as $wide) as Self
+        }
+    };
 }
 
-impl_bitstore! {u32}
-impl_bitstore! {u64}
+impl_bitstore! {u32, u64}
+impl_bitstore! {u64, u128}
 impl_bitstore! {u128}
-impl_bitstore! {usize}
+#[cfg(target_pointer_width = "16")]
+impl_bitstore! {usize, u32}
+#[cfg(target_pointer_width = "32")]
+impl_bitstore! {usize, u64}
+#[cfg(target_pointer_width = "64")]
+impl_bitstore! {usize, u128}
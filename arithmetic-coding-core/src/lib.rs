@@ -1,9 +1,13 @@
 //! Core traits for the [`arithmetic-coding`](https://github.com/danieleades/arithmetic-coding) crate
 
+#![no_std]
 #![deny(missing_docs, missing_debug_implementations)]
 
+#[cfg(feature = "std")]
+extern crate std;
+
 mod bitstore;
-pub use bitstore::BitStore;
+pub use bitstore::{BitStore, Reciprocal};
 
 mod model;
 pub use model::{Model, fixed_length, max_length, one_shot};
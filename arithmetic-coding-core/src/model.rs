@@ -1,4 +1,4 @@
-use std::{error::Error, ops::Range};
+use core::{error::Error, ops::Range};
 
 use crate::BitStore;
 
@@ -15,8 +15,8 @@ pub mod one_shot;
 /// # Example
 ///
 /// ```
-/// # use std::convert::Infallible;
-/// use std::ops::Range;
+/// # use core::convert::Infallible;
+/// use core::ops::Range;
 /// #
 /// # use arithmetic_coding_core::Model;
 ///
@@ -94,13 +94,31 @@ pub trait Model {
     /// which is suitable for non-adaptive models.
     ///
     /// In adaptive models this value may change, however it should never exceed
-    /// [`Model::max_denominator`], or it becomes possible for the
-    /// [`Encoder`](crate::Encoder) and [`Decoder`](crate::Decoder) to panic due
-    /// to overflow or underflow.
+    /// [`Model::max_denominator`], or it becomes possible for the `Encoder` and
+    /// `Decoder` to panic due to overflow or underflow.
     fn denominator(&self) -> Self::B {
         self.max_denominator()
     }
 
+    /// The base-2 logarithm of [`Model::denominator`], if the denominator is
+    /// a power of two.
+    ///
+    /// If this returns `Some(n)`, the denominator must be exactly `2^n`. The
+    /// `Encoder` and `Decoder` can then divide by the denominator with a
+    /// shift.
+    ///
+    /// By default this returns `None`, in which case the coders divide by a
+    /// reciprocal, which is cached for as long as the denominator doesn't
+    /// change.
+    ///
+    /// Either way, decoding still takes one division per symbol, by the
+    /// width of the current interval, to find the value passed to
+    /// [`Model::symbol`]. At the default precision this is a division at
+    /// the width of [`Model::B`], rather than a widening one.
+    fn denominator_log2(&self) -> Option<u32> {
+        None
+    }
+
     /// The maximum denominator used for probability ranges. See
     /// [`Model::probability`].
     ///
@@ -116,6 +134,27 @@ pub trait Model {
     /// This is the inverse of the [`Model::probability`] method
     fn symbol(&self, value: Self::B) -> Option<Self::Symbol>;
 
+    /// Given a value, return the symbol whose probability range it falls in,
+    /// along with that range.
+    ///
+    /// This is equivalent to [`Model::symbol`] followed by
+    /// [`Model::probability`], which is how it's implemented by default.
+    /// Models which search a table for the symbol can override it to find the
+    /// range in the same search.
+    ///
+    /// # Errors
+    ///
+    /// This returns a custom error if the decoded symbol is not valid
+    #[allow(clippy::type_complexity)]
+    fn decode_symbol(
+        &self,
+        value: Self::B,
+    ) -> Result<(Option<Self::Symbol>, Range<Self::B>), Self::ValueError> {
+        let symbol = self.symbol(value);
+        let range = self.probability(symbol.as_ref())?;
+        Ok((symbol, range))
+    }
+
     /// Update the current state of the model with the latest symbol.
     ///
     /// This method only needs to be implemented for 'adaptive' models. It's a
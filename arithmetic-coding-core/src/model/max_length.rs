@@ -1,6 +1,6 @@
 //! Helper trait for creating fixed-length Models
 
-use std::ops::Range;
+use core::ops::Range;
 
 use crate::BitStore;
 
@@ -22,8 +22,8 @@ use crate::BitStore;
 /// # Example
 ///
 /// ```
-/// # use std::convert::Infallible;
-/// # use std::ops::Range;
+/// # use core::convert::Infallible;
+/// # use core::ops::Range;
 /// #
 /// # use arithmetic_coding_core::max_length;
 ///
@@ -73,7 +73,7 @@ pub trait Model {
     type Symbol;
 
     /// Invalid symbol error
-    type ValueError: std::error::Error;
+    type ValueError: core::error::Error;
 
     /// The internal representation to use for storing integers
     type B: BitStore;
@@ -105,13 +105,18 @@ pub trait Model {
     /// which is suitable for non-adaptive models.
     ///
     /// In adaptive models this value may change, however it should never exceed
-    /// [`Model::max_denominator`], or it becomes possible for the
-    /// [`Encoder`](crate::Encoder) and [`Decoder`](crate::Decoder) to panic due
-    /// to overflow or underflow.
+    /// [`Model::max_denominator`], or it becomes possible for the `Encoder` and
+    /// `Decoder` to panic due to overflow or underflow.
     fn denominator(&self) -> Self::B {
         self.max_denominator()
     }
 
+    /// The base-2 logarithm of [`Model::denominator`], if the denominator is
+    /// a power of two. See [`crate::Model::denominator_log2`].
+    fn denominator_log2(&self) -> Option<u32> {
+        None
+    }
+
     /// The maximum denominator used for probability ranges. See
     /// [`Model::probability`].
     ///
@@ -127,6 +132,22 @@ pub trait Model {
     /// This is the inverse of the [`Model::probability`] method
     fn symbol(&self, value: Self::B) -> Option<Self::Symbol>;
 
+    /// Given a value, return the symbol whose probability range it falls in,
+    /// along with that range. See [`crate::Model::decode_symbol`].
+    ///
+    /// # Errors
+    ///
+    /// This returns a custom error if the decoded symbol is not valid
+    #[allow(clippy::type_complexity)]
+    fn decode_symbol(
+        &self,
+        value: Self::B,
+    ) -> Result<(Option<Self::Symbol>, Range<Self::B>), Self::ValueError> {
+        let symbol = self.symbol(value);
+        let range = self.probability(symbol.as_ref())?;
+        Ok((symbol, range))
+    }
+
     /// Update the current state of the model with the latest symbol.
     ///
     /// This method only needs to be implemented for 'adaptive' models. It's a
@@ -197,10 +218,25 @@ where
         }
     }
 
+    fn decode_symbol(
+        &self,
+        value: Self::B,
+    ) -> Result<(Option<Self::Symbol>, Range<Self::B>), Self::ValueError> {
+        if self.remaining > 0 {
+            self.model.decode_symbol(value).map_err(Error::Value)
+        } else {
+            Ok((None, Self::B::ZERO..self.denominator()))
+        }
+    }
+
     fn denominator(&self) -> Self::B {
         self.model.denominator()
     }
 
+    fn denominator_log2(&self) -> Option<u32> {
+        self.model.denominator_log2()
+    }
+
     fn update(&mut self, symbol: Option<&Self::Symbol>) {
         if let Some(s) = symbol {
             self.model.update(s);
@@ -213,7 +249,7 @@ where
 #[derive(Debug, thiserror::Error)]
 pub enum Error<E>
 where
-    E: std::error::Error,
+    E: core::error::Error,
 {
     /// Model received a symbol when it expected an EOF
     #[error("Unexpected Symbol")]
@@ -1,6 +1,6 @@
 //! Helper trait for creating Models which only accept a single symbol
 
-use std::ops::Range;
+use core::ops::Range;
 
 pub use crate::fixed_length::Wrapper;
 use crate::{BitStore, fixed_length};
@@ -23,8 +23,8 @@ use crate::{BitStore, fixed_length};
 /// # Example
 ///
 /// ```
-/// # use std::convert::Infallible;
-/// # use std::ops::Range;
+/// # use core::convert::Infallible;
+/// # use core::ops::Range;
 /// #
 /// # use arithmetic_coding_core::one_shot;
 ///
@@ -68,7 +68,7 @@ pub trait Model {
     type Symbol;
 
     /// Invalid symbol error
-    type ValueError: std::error::Error;
+    type ValueError: core::error::Error;
 
     /// The internal representation to use for storing integers
     type B: BitStore;
@@ -77,7 +77,7 @@ pub trait Model {
     /// symbol occurring.
     ///
     /// This is given as a range, over the denominator given by
-    /// [`Model::denominator`]. This range should in general include `EOF`,
+    /// [`Model::max_denominator`]. This range should in general include `EOF`,
     /// which is denoted by `None`.
     ///
     /// For example, from the set {heads, tails}, the interval representing
@@ -95,8 +95,7 @@ pub trait Model {
     /// [`Model::probability`].
     ///
     /// This value is used to calculate an appropriate precision for the
-    /// encoding, therefore this value must not change, and
-    /// [`Model::denominator`] must never exceed it.
+    /// encoding, therefore this value must not change.
     fn max_denominator(&self) -> Self::B;
 
     /// Given a value, return the symbol whose probability range it falls in.
@@ -0,0 +1,76 @@
+//! Decoding with adaptive models over alphabets of increasing size, where the
+//! symbol search dominates.
+
+use std::hint::black_box;
+
+use arithmetic_coding::{
+    Decoder, Encoder,
+    bits::{SliceReader, VecWriter},
+};
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use fenwick_model::simple::FenwickModel;
+
+const SYMBOLS: usize = 10_000;
+
+/// A deterministic sequence of symbols, skewed towards the start of the
+/// alphabet so that the model adapts unevenly.
+fn input(alphabet: usize) -> Vec<usize> {
+    let mut seed = 0x9e37_79b9_7f4a_7c15_u64;
+    (0..SYMBOLS)
+        .map(|_| {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            let a = usize::try_from(seed % alphabet as u64).unwrap();
+            let b = usize::try_from((seed >> 32) % alphabet as u64).unwrap();
+            a.min(b)
+        })
+        .collect()
+}
+
+fn model(alphabet: usize) -> FenwickModel {
+    FenwickModel::builder(alphabet, 1 << 24).build()
+}
+
+fn encode(alphabet: usize, input: &[usize]) -> Vec<u8> {
+    let mut writer = VecWriter::new();
+    Encoder::new(model(alphabet), &mut writer)
+        .encode_all(input.iter().copied())
+        .unwrap();
+    writer.finish()
+}
+
+fn decode(alphabet: usize, buffer: &[u8]) -> Vec<usize> {
+    Decoder::new(model(alphabet), SliceReader::new(buffer))
+        .decode_all()
+        .map(Result::unwrap)
+        .collect()
+}
+
+#[allow(clippy::missing_panics_doc)]
+pub fn criterion_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("alphabet size");
+    group.throughput(Throughput::Elements(SYMBOLS as u64));
+
+    for alphabet in [16, 256, 4096, 65536] {
+        let input = input(alphabet);
+        let buffer = encode(alphabet, &input);
+        assert_eq!(decode(alphabet, &buffer), input);
+
+        group.bench_with_input(BenchmarkId::new("encode", alphabet), &input, |b, input| {
+            b.iter(|| encode(alphabet, black_box(input)));
+        });
+        group.bench_with_input(
+            BenchmarkId::new("decode", alphabet),
+            &buffer,
+            |b, buffer| {
+                b.iter(|| decode(alphabet, black_box(buffer)));
+            },
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
@@ -1,4 +1,8 @@
-use arithmetic_coding::{Decoder, Encoder, Model};
+use arithmetic_coding::{
+    Decoder, Encoder, Model, ans,
+    bits::{SliceReader, VecWriter},
+    interleaved, range_coder,
+};
 use bitstream_io::{BigEndian, BitReader, BitWrite, BitWriter};
 
 pub fn round_trip<M>(model: M, input: &[M::Symbol])
@@ -38,3 +42,82 @@ where
     let mut decoder = Decoder::new(model, bitreader);
     decoder.decode_all().map(Result::unwrap).collect()
 }
+
+pub fn slice_round_trip<M>(model: M, input: &[M::Symbol])
+where
+    M: Model + Clone,
+    M::Symbol: Copy + std::fmt::Debug + PartialEq,
+{
+    let buffer = slice_encode(model.clone(), input);
+    let output = slice_decode(model, &buffer);
+
+    assert_eq!(input, output.as_slice());
+}
+
+pub fn slice_encode<M>(model: M, input: &[M::Symbol]) -> Vec<u8>
+where
+    M: Model,
+    M::Symbol: Copy,
+{
+    let mut writer = VecWriter::new();
+    Encoder::new(model, &mut writer)
+        .encode_all(input.iter().copied())
+        .unwrap();
+    writer.finish()
+}
+
+pub fn slice_decode<M>(model: M, buffer: &[u8]) -> Vec<M::Symbol>
+where
+    M: Model,
+{
+    let mut decoder = Decoder::new(model, SliceReader::new(buffer));
+    decoder.decode_all().map(Result::unwrap).collect()
+}
+
+pub fn interleaved_round_trip<M, const N: usize>(model: M, input: &[M::Symbol])
+where
+    M: Model + Clone,
+    M::Symbol: Copy + std::fmt::Debug + PartialEq,
+{
+    let mut buffer = Vec::new();
+    interleaved::Encoder::<M, N>::new(model.clone(), &mut buffer)
+        .encode_all(input.iter().copied())
+        .unwrap();
+
+    let mut decoder = interleaved::Decoder::<M, N>::new(model, &buffer);
+    let output: Vec<_> = decoder.decode_all().map(Result::unwrap).collect();
+
+    assert_eq!(input, output.as_slice());
+}
+
+pub fn range_coder_round_trip<M>(model: M, input: &[M::Symbol])
+where
+    M: Model + Clone,
+    M::Symbol: Copy + std::fmt::Debug + PartialEq,
+{
+    let mut buffer = Vec::new();
+    range_coder::Encoder::new(model.clone(), &mut buffer)
+        .encode_all(input.iter().copied())
+        .unwrap();
+
+    let mut decoder = range_coder::Decoder::new(model, &buffer);
+    let output: Vec<_> = decoder.decode_all().map(Result::unwrap).collect();
+
+    assert_eq!(input, output.as_slice());
+}
+
+pub fn ans_round_trip<M>(model: M, input: &[M::Symbol])
+where
+    M: Model + Clone,
+    M::Symbol: Copy + std::fmt::Debug + PartialEq,
+{
+    let mut buffer = Vec::new();
+    ans::Encoder::new(model.clone(), &mut buffer)
+        .encode_all(input.iter().copied())
+        .unwrap();
+
+    let mut decoder = ans::Decoder::new(model, &buffer);
+    let output: Vec<_> = decoder.decode_all().map(Result::unwrap).collect();
+
+    assert_eq!(input, output.as_slice());
+}
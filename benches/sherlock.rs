@@ -1,64 +1,120 @@
-use std::{fs::File, hint::black_box, io::Read, ops::Range};
+use std::{convert::Infallible, fs::File, hint::black_box, io::Read, ops::Range};
 
 use arithmetic_coding::Model;
 use criterion::{Criterion, criterion_group, criterion_main};
-use fenwick_model::{ValueError, simple::FenwickModel};
+use fenwick_model::simple::FenwickModel;
 
 mod common;
 
+fn model() -> FenwickModel<u8> {
+    FenwickModel::builder(256, 1 << 20)
+        .panic_on_saturation()
+        .build()
+}
+
+/// A non-adaptive model of bytes, with frequencies taken from the input and
+/// scaled to a fixed denominator.
 #[derive(Debug, Clone)]
-pub struct StringModel {
-    fenwick_model: FenwickModel,
+struct StaticModel {
+    cumulative: Vec<u64>,
+    denominator_log2: Option<u32>,
 }
 
-impl StringModel {
+impl StaticModel {
+    /// The last symbol is EOF
+    const SYMBOLS: u64 = 257;
+
     #[must_use]
-    pub fn new(symbols: usize) -> Self {
-        let fenwick_model = FenwickModel::builder(symbols, 1 << 20)
-            .panic_on_saturation()
-            .build();
-        Self { fenwick_model }
+    fn new(sample: &[u8], denominator: u64) -> Self {
+        let mut counts = vec![0_u64; 257];
+        for &byte in sample {
+            counts[usize::from(byte)] += 1;
+        }
+        let total: u64 = counts.iter().sum();
+        let spare = denominator - Self::SYMBOLS;
+
+        // every symbol needs a frequency of at least one
+        let mut frequencies: Vec<u64> = counts
+            .iter()
+            .map(|&count| 1 + count * spare / total)
+            .collect();
+        let remainder = denominator - frequencies.iter().sum::<u64>();
+        *frequencies.iter_mut().max().unwrap() += remainder;
+
+        let mut cumulative = vec![0];
+        for frequency in frequencies {
+            cumulative.push(cumulative.last().unwrap() + frequency);
+        }
+
+        Self {
+            cumulative,
+            denominator_log2: None,
+        }
     }
-}
 
-#[derive(Debug, thiserror::Error)]
-#[error("invalid character: {0}")]
-pub struct Error(char);
+    #[must_use]
+    fn power_of_two(sample: &[u8], denominator_log2: u32) -> Self {
+        Self {
+            denominator_log2: Some(denominator_log2),
+            ..Self::new(sample, 1 << denominator_log2)
+        }
+    }
+}
 
-impl Model for StringModel {
+impl Model for StaticModel {
     type B = u64;
     type Symbol = u8;
-    type ValueError = ValueError;
-
-    fn probability(
-        &self,
-        symbol: Option<&Self::Symbol>,
-    ) -> Result<Range<Self::B>, Self::ValueError> {
-        let fenwick_symbol = symbol.map(|c| *c as usize);
-        self.fenwick_model.probability(fenwick_symbol.as_ref())
+    type ValueError = Infallible;
+
+    fn probability(&self, symbol: Option<&u8>) -> Result<Range<u64>, Infallible> {
+        let index = symbol.map_or(256, |&byte| usize::from(byte));
+        Ok(self.cumulative[index]..self.cumulative[index + 1])
     }
 
-    fn symbol(&self, value: Self::B) -> Option<Self::Symbol> {
-        self.fenwick_model
-            .symbol(value)
-            .map(|x| u8::try_from(x).unwrap())
+    fn symbol(&self, value: u64) -> Option<u8> {
+        let index = self.cumulative.partition_point(|&c| c <= value) - 1;
+        u8::try_from(index).ok()
     }
 
-    fn max_denominator(&self) -> Self::B {
-        self.fenwick_model.max_denominator()
+    fn max_denominator(&self) -> u64 {
+        *self.cumulative.last().unwrap()
     }
 
-    fn denominator(&self) -> Self::B {
-        self.fenwick_model.denominator()
+    fn denominator_log2(&self) -> Option<u32> {
+        self.denominator_log2
     }
 }
 
 fn round_trip(input: &[u8]) {
-    let model = StringModel::new(256);
+    let model = model();
 
     common::round_trip(model, input);
 }
 
+fn slice_round_trip(input: &[u8]) {
+    let model = model();
+
+    common::slice_round_trip(model, input);
+}
+
+fn interleaved_round_trip(input: &[u8]) {
+    let model = model();
+
+    common::interleaved_round_trip::<_, 4>(model, input);
+}
+
+fn range_coder_round_trip(input: &[u8]) {
+    let model = model();
+
+    common::range_coder_round_trip(model, input);
+}
+
+fn ans_round_trip(input: &[u8]) {
+    let model = model();
+
+    common::ans_round_trip(model, input);
+}
+
 #[allow(clippy::missing_panics_doc)]
 pub fn criterion_benchmark(c: &mut Criterion) {
     let mut input_string = String::new();
@@ -71,6 +127,40 @@ pub fn criterion_benchmark(c: &mut Criterion) {
     let input = truncated.as_bytes();
 
     c.bench_function("round trip", |b| b.iter(|| round_trip(black_box(input))));
+    c.bench_function("round trip (slice)", |b| {
+        b.iter(|| slice_round_trip(black_box(input)));
+    });
+    c.bench_function("round trip (interleaved x4)", |b| {
+        b.iter(|| interleaved_round_trip(black_box(input)));
+    });
+    c.bench_function("round trip (range coder)", |b| {
+        b.iter(|| range_coder_round_trip(black_box(input)));
+    });
+    c.bench_function("round trip (rANS)", |b| {
+        b.iter(|| ans_round_trip(black_box(input)));
+    });
+
+    // a power-of-two denominator replaces the scaling divisions with shifts,
+    // but decoding still divides by the width of the interval once per symbol
+    let mut group = c.benchmark_group("static model");
+    let power_of_two = StaticModel::power_of_two(input, 16);
+    let arbitrary = StaticModel::new(input, 50_000);
+    for (name, model) in [("power of two", &power_of_two), ("reciprocal", &arbitrary)] {
+        let buffer = common::slice_encode(model.clone(), input);
+        group.bench_function(format!("encode ({name})"), |b| {
+            b.iter(|| common::slice_encode(model.clone(), black_box(input)));
+        });
+        group.bench_function(format!("decode ({name})"), |b| {
+            b.iter(|| common::slice_decode(model.clone(), black_box(&buffer)));
+        });
+    }
+    group.bench_function("round trip (range coder, power of two)", |b| {
+        b.iter(|| common::range_coder_round_trip(power_of_two.clone(), black_box(input)));
+    });
+    group.bench_function("round trip (range coder, division)", |b| {
+        b.iter(|| common::range_coder_round_trip(arbitrary.clone(), black_box(input)));
+    });
+    group.finish();
 }
 
 criterion_group!(benches, criterion_benchmark);
@@ -1,69 +1,16 @@
-use std::{fs::File, io::Read, ops::Range};
+use std::{fs::File, io::Read};
 
-use arithmetic_coding::Model;
+use fenwick_model::{Adaptation, context_switching::FenwickModel};
 
 mod common;
 
-use fenwick_model::{ValueError, context_switching::FenwickModel};
-
-const ALPHABET: &str =
-    "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789 .,\n-':()[]#*;\"!?*&é/àâè%@$";
-
-#[derive(Debug, Clone)]
-pub struct StringModel {
-    alphabet: Vec<char>,
-    fenwick_model: FenwickModel,
-}
-
-impl StringModel {
-    #[must_use]
-    pub fn new(alphabet: Vec<char>) -> Self {
-        let fenwick_model = FenwickModel::with_symbols(alphabet.len(), 1 << 17);
-        Self {
-            alphabet,
-            fenwick_model,
-        }
-    }
-}
-
-#[derive(Debug, thiserror::Error)]
-#[error("invalid character: {0}")]
-pub struct Error(char);
-
-impl Model for StringModel {
-    type B = u64;
-    type Symbol = char;
-    type ValueError = ValueError;
-
-    fn probability(
-        &self,
-        symbol: Option<&Self::Symbol>,
-    ) -> Result<Range<Self::B>, Self::ValueError> {
-        let fenwick_symbol = symbol.map(|c| self.alphabet.iter().position(|x| x == c).unwrap());
-        self.fenwick_model.probability(fenwick_symbol.as_ref())
-    }
-
-    fn symbol(&self, value: Self::B) -> Option<Self::Symbol> {
-        let index = self.fenwick_model.symbol(value)?;
-        self.alphabet.get(index).copied()
-    }
-
-    fn max_denominator(&self) -> Self::B {
-        self.fenwick_model.max_denominator()
-    }
-
-    fn denominator(&self) -> Self::B {
-        self.fenwick_model.denominator()
-    }
-
-    fn update(&mut self, symbol: Option<&Self::Symbol>) {
-        let fenwick_symbol = symbol.map(|c| self.alphabet.iter().position(|x| x == c).unwrap());
-        self.fenwick_model.update(fenwick_symbol.as_ref());
-    }
-}
-
 fn main() {
-    let model = StringModel::new(ALPHABET.chars().collect());
+    // every character in the text is in the first 256 code points. A large
+    // increment stops the initial weights of the many unused characters from
+    // diluting the statistics.
+    let model = FenwickModel::<char>::builder(256, 1 << 20)
+        .adaptation(Adaptation::new().increment(32))
+        .build();
 
     let mut input = String::new();
     File::open("./resources/sherlock.txt")
@@ -0,0 +1,21 @@
+use std::{fs::File, io::Read};
+
+use fenwick_model::{Adaptation, order_k::FenwickModel};
+
+mod common;
+
+fn main() {
+    // every character in the text is in the first 256 code points
+    let model = FenwickModel::<char>::builder(256, 4, 1 << 20)
+        .adaptation(Adaptation::new().increment(64))
+        .max_contexts(1 << 16)
+        .build();
+
+    let mut input = String::new();
+    File::open("./resources/sherlock.txt")
+        .unwrap()
+        .read_to_string(&mut input)
+        .unwrap();
+
+    common::round_trip_string(model, &input);
+}
@@ -1,71 +1,128 @@
 //! Fenwick tree based context-switching model
 
-use arithmetic_coding_core::Model;
+use std::marker::PhantomData;
+
+use arithmetic_coding_core::{BitStore, Model};
 
 use super::Weights;
-use crate::ValueError;
+use crate::{Adaptation, Symbol, ValueError};
 
+/// An adaptive model which keeps separate counts for the symbols following
+/// each symbol, and switches between them
 #[derive(Debug, Clone)]
-pub struct FenwickModel {
-    contexts: Vec<Weights>,
+pub struct FenwickModel<S = usize, B = u64> {
+    contexts: Vec<Weights<B>>,
     current_context: usize,
-    max_denominator: u64,
+    max_denominator: B,
+    adaptation: Adaptation<B>,
+    symbol: PhantomData<S>,
 }
 
-impl FenwickModel {
-    #[must_use]
-    pub fn with_symbols(symbols: usize, max_denominator: u64) -> Self {
+/// A builder for a [`FenwickModel`]
+#[derive(Debug)]
+#[must_use]
+pub struct Builder<S, B> {
+    model: FenwickModel<S, B>,
+}
+
+impl<S: Symbol, B: BitStore> Builder<S, B> {
+    fn new(symbols: usize, max_denominator: B) -> Self {
+        assert!(
+            symbols == 0 || S::from_index(symbols - 1).is_some(),
+            "there are fewer than {symbols} symbols of this type"
+        );
         let mut contexts = Vec::with_capacity(symbols + 1);
 
         for _ in 0..=symbols {
             contexts.push(Weights::new(symbols));
         }
 
-        Self {
+        let model = FenwickModel {
             contexts,
             current_context: 1,
             max_denominator,
-        }
+            adaptation: Adaptation::new(),
+            symbol: PhantomData,
+        };
+        Self { model }
+    }
+
+    /// Set the policy for how each context adapts to the symbols it sees.
+    pub const fn adaptation(mut self, adaptation: Adaptation<B>) -> Self {
+        self.model.adaptation = adaptation;
+        self
+    }
+
+    /// Build the model
+    #[must_use]
+    pub fn build(self) -> FenwickModel<S, B> {
+        self.model
+    }
+}
+
+impl<S: Symbol, B: BitStore> FenwickModel<S, B> {
+    /// Create a model of the first `symbols` symbols of type `S`.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if `S` has fewer than `symbols` symbols.
+    pub fn builder(symbols: usize, max_denominator: B) -> Builder<S, B> {
+        Builder::new(symbols, max_denominator)
+    }
+
+    /// See [`FenwickModel::builder`].
+    #[must_use]
+    pub fn with_symbols(symbols: usize, max_denominator: B) -> Self {
+        Self::builder(symbols, max_denominator).build()
     }
 
-    fn context(&self) -> &Weights {
+    fn context(&self) -> &Weights<B> {
         &self.contexts[self.current_context]
     }
 
-    fn context_mut(&mut self) -> &mut Weights {
+    fn context_mut(&mut self) -> &mut Weights<B> {
         &mut self.contexts[self.current_context]
     }
 }
 
-impl Model for FenwickModel {
-    type B = u64;
-    type Symbol = usize;
-    type ValueError = ValueError;
+impl<S: Symbol, B: BitStore> Model for FenwickModel<S, B> {
+    type B = B;
+    type Symbol = S;
+    type ValueError = ValueError<S>;
 
-    fn probability(&self, symbol: Option<&usize>) -> Result<std::ops::Range<u64>, ValueError> {
-        Ok(self.context().range(symbol.copied()))
+    fn probability(&self, symbol: Option<&S>) -> Result<std::ops::Range<B>, ValueError<S>> {
+        match symbol {
+            None => Ok(self.context().range(None)),
+            Some(s) if s.index() < self.context().len() => {
+                Ok(self.context().range(Some(s.index())))
+            }
+            Some(s) => Err(ValueError(s.clone())),
+        }
     }
 
-    fn denominator(&self) -> u64 {
+    fn denominator(&self) -> B {
         self.context().total
     }
 
-    fn max_denominator(&self) -> u64 {
+    fn max_denominator(&self) -> B {
         self.max_denominator
     }
 
-    fn symbol(&self, value: u64) -> Option<usize> {
-        self.context().symbol(value)
+    fn symbol(&self, value: B) -> Option<S> {
+        self.context().symbol(value).and_then(S::from_index)
     }
 
-    fn update(&mut self, symbol: Option<&usize>) {
-        debug_assert!(
-            self.denominator() < self.max_denominator,
-            "hit max denominator!"
-        );
-        if self.denominator() < self.max_denominator {
-            self.context_mut().update(symbol.copied(), 1);
-        }
-        self.current_context = symbol.map(|x| x + 1).unwrap_or_default();
+    fn decode_symbol(&self, value: B) -> Result<(Option<S>, std::ops::Range<B>), ValueError<S>> {
+        let (index, range) = self.context().decode(value);
+        Ok((index.and_then(S::from_index), range))
+    }
+
+    fn update(&mut self, symbol: Option<&S>) {
+        let index = symbol.map(Symbol::index);
+        let adaptation = self.adaptation;
+        let max_denominator = self.max_denominator;
+        self.context_mut()
+            .adapt(index, &adaptation, max_denominator);
+        self.current_context = index.map(|x| x + 1).unwrap_or_default();
     }
 }
@@ -1,55 +1,189 @@
-//! [`Models`](crate::Model) implemented using Fenwick trees
+//! [`Models`](arithmetic_coding_core::Model) implemented using Fenwick trees
+//!
+//! The models are generic over the [`BitStore`] used for their weights, and
+//! over any type of [`Symbol`] which can be mapped to and from an index.
+
+#![deny(missing_docs, missing_debug_implementations)]
 
 use std::ops::Range;
 
+use arithmetic_coding_core::BitStore;
+
 pub mod context_switching;
+pub mod order_k;
 pub mod simple;
+mod symbol;
+pub mod windowed;
+
+pub use symbol::Symbol;
 
 /// A wrapper around a vector of fenwick counts, with one additional weight for
 /// EOF.
 #[derive(Debug, Clone)]
-struct Weights {
-    fenwick_counts: Vec<u64>,
-    total: u64,
+struct Weights<B> {
+    fenwick_counts: Vec<B>,
+    total: B,
+    /// The number of updates since the weights last decayed
+    since_decay: u64,
 }
 
-impl Weights {
+impl<B: BitStore> Weights<B> {
     fn new(n: usize) -> Self {
         // we add one extra value here to account for the EOF
-        let mut fenwick_counts = vec![0; n + 1];
+        let mut weights = Self {
+            fenwick_counts: vec![B::ZERO; n + 1],
+            total: B::ZERO,
+            since_decay: 0,
+        };
 
-        for i in 0..fenwick_counts.len() {
-            fenwick::array::update(&mut fenwick_counts, i, 1);
-        }
+        weights.map(|_| B::ONE);
+        weights
+    }
 
-        let total = fenwick_counts.len() as u64;
-        Self {
-            fenwick_counts,
-            total,
+    /// Add `delta` to the weight of a symbol.
+    fn update(&mut self, i: Option<usize>, delta: B) {
+        let index = i.map(|i| i + 1).unwrap_or_default();
+        for node in fenwick::index::zero_based::up(index, self.fenwick_counts.len()) {
+            self.fenwick_counts[node] += delta;
         }
+        self.total += delta;
     }
 
-    fn update(&mut self, i: Option<usize>, delta: u64) {
+    /// Subtract `delta` from the weight of a symbol.
+    ///
+    /// The weight must stay above zero, or the symbol could no longer be
+    /// encoded.
+    fn decrement(&mut self, i: Option<usize>, delta: B) {
+        debug_assert!(
+            self.range(i).end - self.range(i).start > delta,
+            "weight must stay above zero"
+        );
+
+        // every node containing the weight is at least as large as it, so none
+        // of these can underflow
         let index = i.map(|i| i + 1).unwrap_or_default();
-        fenwick::array::update(&mut self.fenwick_counts, index, delta);
-        self.total += delta;
+        for node in fenwick::index::zero_based::up(index, self.fenwick_counts.len()) {
+            self.fenwick_counts[node] = self.fenwick_counts[node] - delta;
+        }
+        self.total = self.total - delta;
+    }
+
+    /// Update the weight of a symbol according to an [`Adaptation`] policy.
+    ///
+    /// The weights are rescaled first if the update would take the total past
+    /// the rescale threshold, and the total never exceeds `max_denominator`.
+    ///
+    /// Returns `true` if the weights were rescaled.
+    fn adapt(&mut self, i: Option<usize>, adaptation: &Adaptation<B>, max_denominator: B) -> bool {
+        let threshold = adaptation
+            .rescale_threshold
+            .filter(|&threshold| threshold < max_denominator)
+            .unwrap_or(max_denominator);
+
+        let rescaled = self.total + adaptation.increment > threshold;
+        if rescaled {
+            self.rescale();
+        }
+
+        // if the threshold is too small for the alphabet, even the rescaled
+        // weights might not leave room for the increment
+        if self.total + adaptation.increment <= max_denominator {
+            self.update(i, adaptation.increment);
+        }
+
+        if let Some(decay) = adaptation.decay {
+            self.since_decay += 1;
+            if self.since_decay >= decay.period {
+                self.since_decay = 0;
+                self.map(|weight| {
+                    let decayed = weight - (weight >> decay.shift);
+                    if decayed > B::ZERO { decayed } else { B::ONE }
+                });
+            }
+        }
+
+        rescaled
+    }
+
+    /// Halve every weight, rounding up so that no weight drops to zero.
+    fn rescale(&mut self) {
+        self.map(|weight| (weight >> 1) + (weight & B::ONE));
+    }
+
+    /// Replace every weight (including EOF) with `f(weight)`.
+    ///
+    /// The tree is unpacked into the individual weights and rebuilt in place,
+    /// which takes linear time.
+    fn map(&mut self, f: impl Fn(B) -> B) {
+        let tree = &mut self.fenwick_counts;
+        let len = tree.len();
+
+        // each (one-based) node is added to its parent, so subtract them in
+        // reverse order to recover the weights
+        for node in (1..=len).rev() {
+            let parent = node + (node & node.wrapping_neg());
+            if parent <= len {
+                tree[parent - 1] = tree[parent - 1] - tree[node - 1];
+            }
+        }
+
+        self.total = B::ZERO;
+        for weight in tree.iter_mut() {
+            *weight = f(*weight);
+            self.total += *weight;
+        }
+
+        for node in 1..=len {
+            let parent = node + (node & node.wrapping_neg());
+            if parent <= len {
+                let child = tree[node - 1];
+                tree[parent - 1] += child;
+            }
+        }
     }
 
-    fn prefix_sum(&self, i: Option<usize>) -> u64 {
+    fn prefix_sum(&self, i: Option<usize>) -> B {
         let index = i.map(|i| i + 1).unwrap_or_default();
-        fenwick::array::prefix_sum(&self.fenwick_counts, index)
+        let mut sum = B::ZERO;
+        for node in fenwick::index::zero_based::down(index) {
+            sum += self.fenwick_counts[node];
+        }
+        sum
     }
 
-    fn range(&self, i: Option<usize>) -> Range<u64> {
+    /// The range of a symbol, which is bounded by the prefix sums up to (and
+    /// excluding) the symbol, and up to and including it.
+    ///
+    /// Both prefix sums are walked down the tree together. Once the walks
+    /// meet, the rest of the sum is shared, and only needs to be calculated
+    /// once.
+    fn range(&self, i: Option<usize>) -> Range<B> {
         let index = i.map(|i| i + 1).unwrap_or_default();
+        let tree = &self.fenwick_counts;
 
-        let upper = fenwick::array::prefix_sum(&self.fenwick_counts, index);
+        // one-based node indices
+        let mut upper_node = index + 1;
+        let mut lower_node = index;
+        let mut upper = B::ZERO;
+        let mut lower = B::ZERO;
+
+        while upper_node != lower_node {
+            if upper_node > lower_node {
+                upper += tree[upper_node - 1];
+                upper_node &= upper_node - 1;
+            } else {
+                lower += tree[lower_node - 1];
+                lower_node &= lower_node - 1;
+            }
+        }
+
+        let mut node = upper_node;
+        while node > 0 {
+            lower += tree[node - 1];
+            upper += tree[node - 1];
+            node &= node - 1;
+        }
 
-        let lower = if index == 0 {
-            0
-        } else {
-            fenwick::array::prefix_sum(&self.fenwick_counts, index - 1)
-        };
         lower..upper
     }
 
@@ -57,57 +191,173 @@ impl Weights {
         self.fenwick_counts.len() - 1
     }
 
-    fn symbol(&self, prefix_sum: u64) -> Option<usize> {
-        if prefix_sum < self.prefix_sum(None) {
-            return None;
+    fn symbol(&self, prefix_sum: B) -> Option<usize> {
+        self.decode(prefix_sum).0
+    }
+
+    /// Find the symbol whose range contains the prefix sum, along with its
+    /// range.
+    ///
+    /// This descends the tree from the top, halving the step at each level,
+    /// so it only visits one node per level. The lower bound of the range is
+    /// the sum of the nodes stepped over, and the upper bound is the sum at
+    /// the last node which overshot the prefix sum.
+    fn decode(&self, prefix_sum: B) -> (Option<usize>, Range<B>) {
+        let eof = self.prefix_sum(None);
+        if prefix_sum < eof {
+            return (None, B::ZERO..eof);
         }
 
-        // we seek the lowest number i such that prefix_sum(i) > prefix_sum
-        let mut low = 0;
-        let mut high = self.len();
-        // Ensure the search range is valid (low < high)
-        debug_assert!(low < high, "Invalid search range");
+        debug_assert!(prefix_sum < self.total, "'prefix_sum' is out of bounds");
 
-        // Verify that prefix_sum is within the valid range of cumulative weights
-        debug_assert!(
-            prefix_sum < self.prefix_sum(Some(high - 1)),
-            "'prefix_sum' is out of bounds"
-        );
-        while low + 1 < high {
-            let i = (low + high - 1) / 2;
-            if self.prefix_sum(Some(i)) > prefix_sum {
-                // i could be our answer, so set high just above it.
-                high = i + 1;
-            } else {
-                // i could not be our answer, so set low just above it.
-                low = i + 1;
+        let tree = &self.fenwick_counts;
+        let mut step = 1 << tree.len().ilog2();
+
+        // the number of weights whose cumulative sum is no greater than the
+        // prefix sum, which is the (zero-based) index of the weight containing
+        // it
+        let mut position = 0;
+        let mut lower = B::ZERO;
+        let mut upper = self.total;
+
+        while step > 0 {
+            let next = position + step;
+            if next <= tree.len() {
+                let sum = lower + tree[next - 1];
+                if sum <= prefix_sum {
+                    position = next;
+                    lower = sum;
+                } else {
+                    upper = sum;
+                }
             }
+            step >>= 1;
         }
-        Some(low)
+
+        // the first weight is EOF
+        (Some(position - 1), lower..upper)
     }
 
-    const fn total(&self) -> u64 {
+    const fn total(&self) -> B {
         self.total
     }
 }
 
+/// Convert a `usize` to a [`BitStore`], or `None` if it doesn't fit.
+fn from_usize<B: BitStore>(value: usize) -> Option<B> {
+    let bits = usize::BITS - value.leading_zeros();
+    (bits <= B::BITS).then(|| {
+        value
+            .to_be_bytes()
+            .into_iter()
+            .fold(B::ZERO, |acc, byte| (acc << 8) + B::from(byte))
+    })
+}
+
+/// How the weights of a model adapt to the symbols it sees.
+///
+/// Each time a symbol is seen, its weight is increased by the
+/// [increment](Adaptation::increment). If that would take the total weight
+/// past the [rescale threshold](Adaptation::rescale_threshold), every weight is
+/// first halved (but kept at least 1), so that the model keeps learning
+/// instead of freezing once the denominator reaches its maximum. Halving also
+/// makes recent symbols count for more than older ones.
+///
+/// Old statistics can additionally be faded out more smoothly with
+/// [exponential decay](Adaptation::decay).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Adaptation<B = u64> {
+    increment: B,
+    rescale_threshold: Option<B>,
+    decay: Option<Decay>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Decay {
+    period: u64,
+    shift: u32,
+}
+
+impl<B: BitStore> Adaptation<B> {
+    /// The default policy, which increments weights by 1, rescales at the
+    /// model's maximum denominator, and doesn't decay.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            increment: B::ONE,
+            rescale_threshold: None,
+            decay: None,
+        }
+    }
+
+    /// Set the amount a symbol's weight is increased by each time it's seen.
+    ///
+    /// Larger increments adapt faster, since the initial weights count for
+    /// less, and cause more frequent rescaling.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if `increment` is zero.
+    #[must_use]
+    pub fn increment(mut self, increment: B) -> Self {
+        assert!(increment > B::ZERO, "increment must be greater than zero");
+        self.increment = increment;
+        self
+    }
+
+    /// Set the total weight past which the weights are halved.
+    ///
+    /// By default this is the model's maximum denominator. A threshold larger
+    /// than that is clamped to it.
+    #[must_use]
+    pub const fn rescale_threshold(mut self, threshold: B) -> Self {
+        self.rescale_threshold = Some(threshold);
+        self
+    }
+
+    /// Decay the weights every `period` updates, by scaling each of them by
+    /// `1 - 2^-shift` (but keeping them at least 1).
+    ///
+    /// # Panics
+    ///
+    /// This method panics if `period` is zero, or if `shift` is zero or not
+    /// less than the number of bits in `B`.
+    #[must_use]
+    pub const fn decay(mut self, period: u64, shift: u32) -> Self {
+        assert!(period > 0, "decay period must be greater than zero");
+        assert!(
+            shift > 0 && shift < B::BITS,
+            "decay shift must be between 1 and the number of bits in the weights"
+        );
+        self.decay = Some(Decay { period, shift });
+        self
+    }
+}
+
+impl<B: BitStore> Default for Adaptation<B> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The error returned for a symbol which is outside a model's alphabet
 #[derive(Debug, thiserror::Error)]
-#[error("invalid symbol received: {0}")]
-pub struct ValueError(pub usize);
+#[error("invalid symbol received: {0:?}")]
+pub struct ValueError<S = usize>(pub S);
 
 #[cfg(test)]
 mod tests {
-    use super::Weights;
+    use super::{Adaptation, Weights, from_usize};
 
     #[test]
     fn total() {
-        let weights = Weights::new(3);
+        let weights = Weights::<u64>::new(3);
         assert_eq!(weights.total(), 4);
     }
 
     #[test]
     fn range() {
-        let weights = Weights::new(3);
+        let weights = Weights::<u64>::new(3);
         assert_eq!(weights.range(None), 0..1);
         assert_eq!(weights.range(Some(0)), 1..2);
         assert_eq!(weights.range(Some(1)), 2..3);
@@ -117,23 +367,155 @@ mod tests {
     #[test]
     #[should_panic(expected = "index out of bounds: the len is 4 but the index is 4")]
     fn range_out_of_bounds() {
-        let weights = Weights::new(3);
+        let weights = Weights::<u64>::new(3);
         weights.range(Some(3));
     }
 
     #[test]
     fn symbol() {
-        let weights = Weights::new(3);
+        let weights = Weights::<u64>::new(3);
         assert_eq!(weights.symbol(0), None);
         assert_eq!(weights.symbol(1), Some(0));
         assert_eq!(weights.symbol(2), Some(1));
         assert_eq!(weights.symbol(3), Some(2));
     }
 
+    #[test]
+    fn decode() {
+        let mut weights = Weights::<u64>::new(3);
+        weights.update(Some(1), 3);
+        assert_eq!(weights.decode(0), (None, 0..1));
+        assert_eq!(weights.decode(1), (Some(0), 1..2));
+        for value in 2..6 {
+            assert_eq!(weights.decode(value), (Some(1), 2..6));
+        }
+        assert_eq!(weights.decode(6), (Some(2), 6..7));
+    }
+
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    fn usize_conversion() {
+        assert_eq!(from_usize::<u32>(0x1234_5678), Some(0x1234_5678));
+        assert_eq!(from_usize::<u32>(u32::MAX as usize), Some(u32::MAX));
+        assert_eq!(from_usize::<u32>(u32::MAX as usize + 1), None);
+        assert_eq!(from_usize::<u64>(usize::MAX), Some(u64::MAX));
+        assert_eq!(from_usize::<u128>(usize::MAX), Some(u128::from(u64::MAX)));
+    }
+
+    /// Compare the tree against a plain array of weights, for every alphabet
+    /// size up to 300.
+    #[test]
+    fn matches_linear_search() {
+        let mut seed = 0x2545_f491_4f6c_dd1d_u64;
+        let mut random = move || {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            seed
+        };
+
+        for n in 1..300 {
+            let mut weights = Weights::<u64>::new(n);
+            let mut counts = vec![1; n + 1];
+            for _ in 0..n {
+                let symbol = usize::try_from(random() % (n as u64 + 1)).unwrap();
+                let delta = random() % 10;
+                weights.update(symbol.checked_sub(1), delta);
+                counts[symbol] += delta;
+            }
+
+            let mut lower = 0;
+            for (index, count) in counts.into_iter().enumerate() {
+                let symbol = index.checked_sub(1);
+                let range = lower..lower + count;
+                assert_eq!(weights.range(symbol), range);
+                for value in range.clone() {
+                    assert_eq!(weights.decode(value), (symbol, range.clone()));
+                }
+                lower = range.end;
+            }
+            assert_eq!(weights.total(), lower);
+        }
+    }
+
+    #[test]
+    fn decrement() {
+        let mut weights = Weights::<u64>::new(3);
+        weights.update(Some(1), 5);
+        weights.update(Some(2), 2);
+        weights.decrement(Some(1), 3);
+        assert_eq!(weights.range(Some(0)), 1..2);
+        assert_eq!(weights.range(Some(1)), 2..5);
+        assert_eq!(weights.range(Some(2)), 5..8);
+        assert_eq!(weights.total(), 8);
+        assert_eq!(weights.decode(4), (Some(1), 2..5));
+    }
+
+    #[test]
+    #[should_panic(expected = "weight must stay above zero")]
+    fn weight_below_one() {
+        let mut weights = Weights::<u64>::new(3);
+        weights.update(Some(1), 2);
+        weights.decrement(Some(1), 3);
+    }
+
+    #[test]
+    fn rescale() {
+        let mut weights = Weights::<u64>::new(3);
+        weights.update(Some(0), 4);
+        weights.update(Some(2), 9);
+        weights.rescale();
+        assert_eq!(weights.range(None), 0..1);
+        assert_eq!(weights.range(Some(0)), 1..4);
+        assert_eq!(weights.range(Some(1)), 4..5);
+        assert_eq!(weights.range(Some(2)), 5..10);
+        assert_eq!(weights.total(), 10);
+    }
+
+    #[test]
+    fn adapt() {
+        let adaptation = Adaptation::new().increment(4);
+        let mut weights = Weights::<u64>::new(3);
+
+        assert!(!weights.adapt(Some(1), &adaptation, 12));
+        assert!(!weights.adapt(Some(1), &adaptation, 12));
+        assert_eq!(weights.range(Some(1)), 2..11);
+
+        // the next update would take the total past 12
+        assert!(weights.adapt(Some(1), &adaptation, 12));
+        assert_eq!(weights.range(Some(1)), 2..11);
+        assert_eq!(weights.total(), 12);
+    }
+
+    #[test]
+    fn rescale_threshold() {
+        let adaptation = Adaptation::new().rescale_threshold(6);
+        let mut weights = Weights::<u64>::new(3);
+
+        for _ in 0..2 {
+            assert!(!weights.adapt(Some(2), &adaptation, 100));
+        }
+        assert!(weights.adapt(Some(2), &adaptation, 100));
+        assert_eq!(weights.range(Some(2)), 3..6);
+    }
+
+    #[test]
+    fn decay() {
+        let adaptation = Adaptation::new().decay(2, 1);
+        let mut weights = Weights::<u64>::new(1);
+
+        for _ in 0..7 {
+            weights.adapt(Some(0), &adaptation, 100);
+        }
+        // 2 -> 3 -> 2 -> 3 -> 2 -> 3 -> 2 -> 3
+        assert_eq!(weights.range(Some(0)), 1..4);
+        assert_eq!(weights.range(None), 0..1);
+    }
+
     #[test]
     #[should_panic(expected = "'prefix_sum' is out of bounds")]
     fn symbol_out_of_bounds() {
-        let weights = Weights::new(3);
+        let weights = Weights::<u64>::new(3);
         weights.symbol(4);
     }
 }
@@ -0,0 +1,279 @@
+//! Fenwick tree based model which conditions on the previous `k` symbols
+//!
+//! This generalises the [context switching](crate::context_switching) model,
+//! which conditions on just the previous symbol, to contexts of any length
+//! (the 'order' of the model).
+//!
+//! The number of possible contexts grows exponentially with the order, so
+//! instead of a dense set of weights for every context, the contexts of each
+//! order are kept in a fixed-size hash table. A context which collides with
+//! another simply replaces it, so memory stays bounded however long the input
+//! is. Until a context has been seen often enough to be trusted, the model
+//! falls back to the longest shorter context which has.
+
+use std::{collections::VecDeque, marker::PhantomData};
+
+use arithmetic_coding_core::{BitStore, Model};
+
+use super::Weights;
+use crate::{Adaptation, Symbol, ValueError};
+
+/// An adaptive model which predicts each symbol from the previous `k` symbols
+#[derive(Debug, Clone)]
+pub struct FenwickModel<S = usize, B = u64> {
+    n_symbols: usize,
+    order: usize,
+    /// The weights of the empty context, which is always available
+    order0: Weights<B>,
+    /// A hash table of contexts for each order from 1 to `order`
+    tables: Vec<Vec<Option<Context<B>>>>,
+    /// The most recent symbols, most recent first
+    history: VecDeque<usize>,
+    /// The hash of the current context of each order from 1, for as many
+    /// orders as there are symbols in the history
+    hashes: Vec<u64>,
+    /// The order of the context used for the next symbol
+    current: usize,
+    max_denominator: B,
+    adaptation: Adaptation<B>,
+    confidence: u64,
+    symbol: PhantomData<S>,
+}
+
+#[derive(Debug, Clone)]
+struct Context<B> {
+    hash: u64,
+    weights: Weights<B>,
+    /// The number of times this context has been updated
+    seen: u64,
+}
+
+/// A builder for a [`FenwickModel`]
+#[derive(Debug)]
+#[must_use]
+pub struct Builder<S, B> {
+    n_symbols: usize,
+    order: usize,
+    max_denominator: B,
+    adaptation: Adaptation<B>,
+    max_contexts: usize,
+    confidence: u64,
+    symbol: PhantomData<S>,
+}
+
+impl<S: Symbol, B: BitStore> Builder<S, B> {
+    fn new(n_symbols: usize, order: usize, max_denominator: B) -> Self {
+        assert!(
+            n_symbols == 0 || S::from_index(n_symbols - 1).is_some(),
+            "there are fewer than {n_symbols} symbols of this type"
+        );
+        Self {
+            n_symbols,
+            order,
+            max_denominator,
+            adaptation: Adaptation::new(),
+            max_contexts: 1 << 12,
+            confidence: 16,
+            symbol: PhantomData,
+        }
+    }
+
+    /// Set the policy for how each context adapts to the symbols it sees.
+    ///
+    /// Each context only sees a fraction of the input, so an increment much
+    /// larger than 1 helps the contexts learn quickly.
+    pub const fn adaptation(mut self, adaptation: Adaptation<B>) -> Self {
+        self.adaptation = adaptation;
+        self
+    }
+
+    /// Set the number of contexts stored for each order (above zero).
+    ///
+    /// Each context holds a weight for every symbol, so this bounds the memory
+    /// used by the model. The default is 4096. Larger tables suffer fewer
+    /// collisions, which matters more as the order increases.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if `max_contexts` is zero.
+    pub const fn max_contexts(mut self, max_contexts: usize) -> Self {
+        assert!(
+            max_contexts > 0,
+            "there must be room for at least one context"
+        );
+        self.max_contexts = max_contexts;
+        self
+    }
+
+    /// Set the number of times a context must have been seen before it's used
+    /// to predict symbols. Until then, the model falls back to a shorter
+    /// context.
+    ///
+    /// The default is 16.
+    pub const fn confidence(mut self, confidence: u64) -> Self {
+        self.confidence = confidence;
+        self
+    }
+
+    /// Build the model
+    #[must_use]
+    pub fn build(self) -> FenwickModel<S, B> {
+        let tables = (0..self.order)
+            .map(|_| vec![None; self.max_contexts])
+            .collect();
+
+        FenwickModel {
+            n_symbols: self.n_symbols,
+            order: self.order,
+            order0: Weights::new(self.n_symbols),
+            tables,
+            history: VecDeque::with_capacity(self.order + 1),
+            hashes: Vec::with_capacity(self.order),
+            current: 0,
+            max_denominator: self.max_denominator,
+            adaptation: self.adaptation,
+            confidence: self.confidence,
+            symbol: PhantomData,
+        }
+    }
+}
+
+impl<S: Symbol, B: BitStore> FenwickModel<S, B> {
+    /// Create a model of the first `n_symbols` symbols of type `S`, which
+    /// conditions on up to `order` previous symbols.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if `S` has fewer than `n_symbols` symbols.
+    pub fn builder(n_symbols: usize, order: usize, max_denominator: B) -> Builder<S, B> {
+        Builder::new(n_symbols, order, max_denominator)
+    }
+
+    /// The order of the context which will be used to predict the next symbol.
+    ///
+    /// This is less than the order of the model at the start of the input, and
+    /// wherever the longer contexts haven't been seen often enough.
+    #[must_use]
+    pub const fn current_order(&self) -> usize {
+        self.current
+    }
+
+    fn context(&self) -> &Weights<B> {
+        if self.current == 0 {
+            return &self.order0;
+        }
+        let order = self.current;
+        let hash = self.hashes[order - 1];
+        let slot = self.slot(hash);
+        &self.tables[order - 1][slot]
+            .as_ref()
+            .expect("the current context is stored")
+            .weights
+    }
+
+    // the remainder is less than the table size, so it fits in a `usize`
+    #[allow(clippy::cast_possible_truncation)]
+    fn slot(&self, hash: u64) -> usize {
+        (hash % self.tables[0].len() as u64) as usize
+    }
+
+    /// Hash the context of each order, by mixing in one more symbol of the
+    /// history for each order.
+    fn rehash(&mut self) {
+        self.hashes.clear();
+        let mut hash = 0_u64;
+        for &index in self.history.iter().take(self.order) {
+            hash = (hash ^ index as u64)
+                .wrapping_add(1)
+                .wrapping_mul(0x9e37_79b9_7f4a_7c15)
+                .rotate_left(29);
+            self.hashes.push(hash);
+        }
+    }
+
+    /// Pick the longest context which has been seen often enough to use.
+    fn select(&mut self) {
+        self.current = (1..=self.hashes.len())
+            .rev()
+            .find(|&order| {
+                let hash = self.hashes[order - 1];
+                let slot = self.slot(hash);
+                self.tables[order - 1][slot]
+                    .as_ref()
+                    .is_some_and(|context| context.hash == hash && context.seen >= self.confidence)
+            })
+            .unwrap_or(0);
+    }
+}
+
+impl<S: Symbol, B: BitStore> Model for FenwickModel<S, B> {
+    type B = B;
+    type Symbol = S;
+    type ValueError = ValueError<S>;
+
+    fn probability(&self, symbol: Option<&S>) -> Result<std::ops::Range<B>, ValueError<S>> {
+        match symbol {
+            None => Ok(self.context().range(None)),
+            Some(s) if s.index() < self.n_symbols => Ok(self.context().range(Some(s.index()))),
+            Some(s) => Err(ValueError(s.clone())),
+        }
+    }
+
+    fn denominator(&self) -> B {
+        self.context().total()
+    }
+
+    fn max_denominator(&self) -> B {
+        self.max_denominator
+    }
+
+    fn symbol(&self, value: B) -> Option<S> {
+        self.context().symbol(value).and_then(S::from_index)
+    }
+
+    fn decode_symbol(&self, value: B) -> Result<(Option<S>, std::ops::Range<B>), ValueError<S>> {
+        let (index, range) = self.context().decode(value);
+        Ok((index.and_then(S::from_index), range))
+    }
+
+    fn update(&mut self, symbol: Option<&S>) {
+        let index = symbol.map(Symbol::index);
+
+        // every order learns from the symbol, not just the one used to
+        // predict it, so that longer contexts build up their statistics
+        self.order0
+            .adapt(index, &self.adaptation, self.max_denominator);
+        for order in 1..=self.hashes.len() {
+            let hash = self.hashes[order - 1];
+            let slot = self.slot(hash);
+            let entry = &mut self.tables[order - 1][slot];
+
+            let context = match entry {
+                Some(context) if context.hash == hash => context,
+                Some(context) => {
+                    // evict the colliding context, reusing its allocation
+                    context.hash = hash;
+                    context.weights.map(|_| B::ONE);
+                    context.seen = 0;
+                    context
+                }
+                None => entry.insert(Context {
+                    hash,
+                    weights: Weights::new(self.n_symbols),
+                    seen: 0,
+                }),
+            };
+            context
+                .weights
+                .adapt(index, &self.adaptation, self.max_denominator);
+            context.seen += 1;
+        }
+
+        if let Some(index) = index {
+            self.history.push_front(index);
+            self.history.truncate(self.order);
+        }
+        self.rehash();
+        self.select();
+    }
+}
@@ -1,55 +1,80 @@
-#![allow(missing_docs, unused)]
 //! simple adaptive model using a fenwick tree
 
-use arithmetic_coding_core::Model;
+use std::marker::PhantomData;
+
+use arithmetic_coding_core::{BitStore, Model};
 
 use super::Weights;
-use crate::ValueError;
+use crate::{Adaptation, Symbol, ValueError};
 
+/// An adaptive model which counts every symbol it sees
 #[derive(Debug, Clone)]
-pub struct FenwickModel {
-    weights: Weights,
-    max_denominator: u64,
+pub struct FenwickModel<S = usize, B = u64> {
+    weights: Weights<B>,
+    max_denominator: B,
+    adaptation: Adaptation<B>,
     panic_on_saturation: bool,
+    symbol: PhantomData<S>,
 }
 
+/// A builder for a [`FenwickModel`]
+#[derive(Debug)]
 #[must_use]
-pub struct Builder {
-    model: FenwickModel,
+pub struct Builder<S, B> {
+    model: FenwickModel<S, B>,
 }
 
-impl Builder {
-    fn new(n_symbols: usize, max_denominator: u64) -> Self {
+impl<S: Symbol, B: BitStore> Builder<S, B> {
+    fn new(n_symbols: usize, max_denominator: B) -> Self {
+        assert!(
+            n_symbols == 0 || S::from_index(n_symbols - 1).is_some(),
+            "there are fewer than {n_symbols} symbols of this type"
+        );
         let weights = Weights::new(n_symbols);
         let model = FenwickModel {
             weights,
             max_denominator,
+            adaptation: Adaptation::new(),
             panic_on_saturation: false,
+            symbol: PhantomData,
         };
         Self { model }
     }
 
+    /// Set the policy for how the model adapts to the symbols it sees.
+    pub const fn adaptation(mut self, adaptation: Adaptation<B>) -> Self {
+        self.model.adaptation = adaptation;
+        self
+    }
+
+    /// Panic (in debug builds) if the model ever needs to rescale its weights.
     pub const fn panic_on_saturation(mut self) -> Self {
         self.model.panic_on_saturation = true;
         self
     }
 
+    /// Build the model
     #[must_use]
-    pub fn build(self) -> FenwickModel {
+    pub fn build(self) -> FenwickModel<S, B> {
         self.model
     }
 }
 
-impl FenwickModel {
-    pub fn builder(n_symbols: usize, max_denominator: u64) -> Builder {
+impl<S: Symbol, B: BitStore> FenwickModel<S, B> {
+    /// Create a model of the first `n_symbols` symbols of type `S`.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if `S` has fewer than `n_symbols` symbols.
+    pub fn builder(n_symbols: usize, max_denominator: B) -> Builder<S, B> {
         Builder::new(n_symbols, max_denominator)
     }
 }
 
-impl Model for FenwickModel {
-    type B = u64;
-    type Symbol = usize;
-    type ValueError = ValueError;
+impl<S: Symbol, B: BitStore> Model for FenwickModel<S, B> {
+    type B = B;
+    type Symbol = S;
+    type ValueError = ValueError<S>;
 
     fn probability(
         &self,
@@ -57,8 +82,8 @@ impl Model for FenwickModel {
     ) -> Result<std::ops::Range<Self::B>, Self::ValueError> {
         match symbol {
             None => Ok(self.weights.range(None)),
-            Some(&s) if s < self.weights.len() => Ok(self.weights.range(Some(s))),
-            Some(&s) => Err(ValueError(s)),
+            Some(s) if s.index() < self.weights.len() => Ok(self.weights.range(Some(s.index()))),
+            Some(s) => Err(ValueError(s.clone())),
         }
     }
 
@@ -67,7 +92,15 @@ impl Model for FenwickModel {
     }
 
     fn symbol(&self, value: Self::B) -> Option<Self::Symbol> {
-        self.weights.symbol(value)
+        self.weights.symbol(value).and_then(S::from_index)
+    }
+
+    fn decode_symbol(
+        &self,
+        value: Self::B,
+    ) -> Result<(Option<Self::Symbol>, std::ops::Range<Self::B>), Self::ValueError> {
+        let (index, range) = self.weights.decode(value);
+        Ok((index.and_then(S::from_index), range))
     }
 
     fn denominator(&self) -> Self::B {
@@ -75,14 +108,13 @@ impl Model for FenwickModel {
     }
 
     fn update(&mut self, symbol: Option<&Self::Symbol>) {
+        let rescaled = self.weights.adapt(
+            symbol.map(Symbol::index),
+            &self.adaptation,
+            self.max_denominator,
+        );
         if self.panic_on_saturation {
-            debug_assert!(
-                self.denominator() < self.max_denominator,
-                "hit max denominator!"
-            );
-        }
-        if self.denominator() < self.max_denominator {
-            self.weights.update(symbol.copied(), 1);
+            debug_assert!(!rescaled, "hit max denominator!");
         }
     }
 }
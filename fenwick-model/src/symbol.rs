@@ -0,0 +1,102 @@
+/// A type of symbol which can be modelled by a Fenwick tree.
+///
+/// Each symbol maps to a unique index, and back again. The indices must be
+/// contiguous, starting from zero, so that a model of `n` symbols covers the
+/// symbols with indices `0..n`.
+///
+/// This is implemented for the unsigned integer types (up to `usize`) and for
+/// `char`. It can be implemented for an enum by numbering its variants.
+///
+/// # Example
+///
+/// ```
+/// use fenwick_model::Symbol;
+///
+/// #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// enum Base {
+///     A,
+///     C,
+///     G,
+///     T,
+/// }
+///
+/// impl Symbol for Base {
+///     fn index(&self) -> usize {
+///         *self as usize
+///     }
+///
+///     fn from_index(index: usize) -> Option<Self> {
+///         [Self::A, Self::C, Self::G, Self::T].get(index).copied()
+///     }
+/// }
+/// ```
+pub trait Symbol: Clone + core::fmt::Debug {
+    /// The index of this symbol
+    fn index(&self) -> usize;
+
+    /// The symbol with the given index, or `None` if there isn't one
+    fn from_index(index: usize) -> Option<Self>;
+}
+
+macro_rules! impl_symbol {
+    ($($t:ty),*) => {
+        $(
+            impl Symbol for $t {
+                fn index(&self) -> usize {
+                    usize::try_from(*self).expect("symbol is too large to be indexed")
+                }
+
+                fn from_index(index: usize) -> Option<Self> {
+                    Self::try_from(index).ok()
+                }
+            }
+        )*
+    };
+}
+
+impl_symbol!(u8, u16, u32, usize);
+
+/// The surrogate code points, which aren't valid `char`s
+const SURROGATES: core::ops::Range<u32> = 0xD800..0xE000;
+
+/// `char`s are indexed by their code point, skipping over the surrogates so
+/// that the indices are contiguous.
+impl Symbol for char {
+    fn index(&self) -> usize {
+        let mut code_point = u32::from(*self);
+        if code_point >= SURROGATES.end {
+            code_point -= SURROGATES.end - SURROGATES.start;
+        }
+        code_point as usize
+    }
+
+    fn from_index(index: usize) -> Option<Self> {
+        let mut code_point = u32::try_from(index).ok()?;
+        if code_point >= SURROGATES.start {
+            code_point = code_point.checked_add(SURROGATES.end - SURROGATES.start)?;
+        }
+        Self::from_u32(code_point)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Symbol;
+
+    #[test]
+    fn integers() {
+        assert_eq!(200_u8.index(), 200);
+        assert_eq!(u8::from_index(200), Some(200));
+        assert_eq!(u8::from_index(256), None);
+        assert_eq!(usize::from_index(usize::MAX), Some(usize::MAX));
+    }
+
+    #[test]
+    fn chars() {
+        for c in ['\0', 'a', 'é', '\u{D7FF}', '\u{E000}', char::MAX] {
+            assert_eq!(char::from_index(c.index()), Some(c));
+        }
+        assert_eq!('\u{E000}'.index(), 0xD800);
+        assert_eq!(char::from_index(char::MAX.index() + 1), None);
+    }
+}
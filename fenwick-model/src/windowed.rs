@@ -0,0 +1,183 @@
+//! Fenwick tree based model of a sliding window of recent symbols
+//!
+//! Unlike the [simple](crate::simple) model, whose statistics reflect every
+//! symbol it has ever seen, this model only counts the last `window` symbols.
+//! This suits non-stationary inputs, such as logs or telemetry, where the
+//! distribution of symbols drifts over time.
+
+use std::marker::PhantomData;
+
+use arithmetic_coding_core::{BitStore, Model};
+
+use super::Weights;
+use crate::{Symbol, ValueError, from_usize};
+
+/// An adaptive model which counts the symbols in a sliding window
+#[derive(Debug, Clone)]
+pub struct FenwickModel<S = usize, B = u64> {
+    weights: Weights<B>,
+    /// A ring buffer of the most recent symbols (see [`pack`])
+    recent: Vec<u32>,
+    /// The position of the oldest symbol in the ring buffer, once it's full
+    oldest: usize,
+    window: usize,
+    increment: B,
+    max_denominator: B,
+    symbol: PhantomData<S>,
+}
+
+/// A builder for a [`FenwickModel`]
+#[derive(Debug)]
+#[must_use]
+pub struct Builder<S, B> {
+    n_symbols: usize,
+    window: usize,
+    increment: B,
+    symbol: PhantomData<S>,
+}
+
+impl<S: Symbol, B: BitStore> Builder<S, B> {
+    fn new(n_symbols: usize, window: usize) -> Self {
+        assert!(window > 0, "window must not be empty");
+        assert!(
+            u32::try_from(n_symbols).is_ok(),
+            "there are too many symbols to store in the window"
+        );
+        assert!(
+            n_symbols == 0 || S::from_index(n_symbols - 1).is_some(),
+            "there are fewer than {n_symbols} symbols of this type"
+        );
+        Self {
+            n_symbols,
+            window,
+            increment: B::ONE,
+            symbol: PhantomData,
+        }
+    }
+
+    /// Set the amount a symbol's weight is increased by while it's in the
+    /// window.
+    ///
+    /// Larger increments make the initial weight of each symbol count for
+    /// less.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if `increment` is zero.
+    pub fn increment(mut self, increment: B) -> Self {
+        assert!(increment > B::ZERO, "increment must be greater than zero");
+        self.increment = increment;
+        self
+    }
+
+    /// Build the model
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the window is too large to be represented by the
+    /// [`BitStore`], or if the largest denominator of the model (the initial
+    /// weights plus the increment for every symbol in the window) overflows
+    /// it.
+    #[must_use]
+    pub fn build(self) -> FenwickModel<S, B> {
+        let weights = Weights::new(self.n_symbols);
+
+        // the denominator is largest once the window is full, when it's the
+        // initial weights plus an increment for every symbol in the window
+        let window: B = from_usize(self.window).expect("window is too large for the BitStore");
+        let max_denominator = window
+            .checked_mul(self.increment)
+            .and_then(|increments| increments.checked_add(weights.total()))
+            .expect("maximum denominator overflows the BitStore");
+
+        FenwickModel {
+            weights,
+            // the window may be much larger than the input, so the buffer
+            // grows as symbols are seen
+            recent: Vec::new(),
+            oldest: 0,
+            window: self.window,
+            increment: self.increment,
+            max_denominator,
+            symbol: PhantomData,
+        }
+    }
+}
+
+impl<S: Symbol, B: BitStore> FenwickModel<S, B> {
+    /// Create a model of the first `n_symbols` symbols of type `S`, which
+    /// counts the last `window` symbols.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if `window` is zero, if `S` has fewer than
+    /// `n_symbols` symbols, or if `n_symbols` doesn't fit in a `u32`.
+    pub fn builder(n_symbols: usize, window: usize) -> Builder<S, B> {
+        Builder::new(n_symbols, window)
+    }
+}
+
+impl<S: Symbol, B: BitStore> Model for FenwickModel<S, B> {
+    type B = B;
+    type Symbol = S;
+    type ValueError = ValueError<S>;
+
+    fn probability(
+        &self,
+        symbol: Option<&Self::Symbol>,
+    ) -> Result<std::ops::Range<Self::B>, Self::ValueError> {
+        match symbol {
+            None => Ok(self.weights.range(None)),
+            Some(s) if s.index() < self.weights.len() => Ok(self.weights.range(Some(s.index()))),
+            Some(s) => Err(ValueError(s.clone())),
+        }
+    }
+
+    fn max_denominator(&self) -> Self::B {
+        self.max_denominator
+    }
+
+    fn symbol(&self, value: Self::B) -> Option<Self::Symbol> {
+        self.weights.symbol(value).and_then(S::from_index)
+    }
+
+    fn decode_symbol(
+        &self,
+        value: Self::B,
+    ) -> Result<(Option<Self::Symbol>, std::ops::Range<Self::B>), Self::ValueError> {
+        let (index, range) = self.weights.decode(value);
+        Ok((index.and_then(S::from_index), range))
+    }
+
+    fn denominator(&self) -> Self::B {
+        self.weights.total()
+    }
+
+    fn update(&mut self, symbol: Option<&Self::Symbol>) {
+        let index = symbol.map(Symbol::index);
+
+        if self.recent.len() < self.window {
+            self.recent.push(pack(index));
+        } else {
+            let oldest = std::mem::replace(&mut self.recent[self.oldest], pack(index));
+            self.weights.decrement(unpack(oldest), self.increment);
+            self.oldest = (self.oldest + 1) % self.window;
+        }
+
+        self.weights.update(index, self.increment);
+    }
+}
+
+/// Store a symbol index in the ring buffer, as one more than the index, with
+/// zero for EOF.
+fn pack(index: Option<usize>) -> u32 {
+    // the builder checks that every symbol index fits
+    index.map_or(0, |i| {
+        u32::try_from(i + 1).expect("symbol index is too large")
+    })
+}
+
+/// The inverse of [`pack`]
+fn unpack(packed: u32) -> Option<usize> {
+    packed.checked_sub(1).map(|i| i as usize)
+}
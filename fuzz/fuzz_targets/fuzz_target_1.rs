@@ -5,8 +5,7 @@ use libfuzzer_sys::fuzz_target;
 mod round_trip;
 
 fuzz_target!(|data: &[u8]| {
-    let model = FenwickModel::builder(256, 1 << 20).build();
-    let input: Vec<usize> = data.into_iter().copied().map(usize::from).collect();
+    let model = FenwickModel::<u8>::builder(256, 1 << 20).build();
 
-    round_trip::round_trip(model, input);
+    round_trip::round_trip(model, data.to_vec());
 });
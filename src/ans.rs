@@ -0,0 +1,113 @@
+//! An asymmetric numeral system (rANS) entropy coder.
+//!
+//! rANS is an alternative to arithmetic coding which achieves a similar
+//! compression ratio, but with a much cheaper decoding loop. Each step of the
+//! decoder requires only a shift, a mask and a multiplication, rather than the
+//! divisions required by the arithmetic [`Decoder`](crate::Decoder).
+//!
+//! The catch is that rANS is a 'last-in-first-out' coder- symbols must be
+//! encoded in the reverse of the order in which they are decoded. The
+//! [`Encoder`] handles this for you by buffering the probability of each
+//! symbol, then encoding them all in reverse when it is flushed. This means
+//! the [`Encoder`] works with any [`Model`] (including adaptive models), since
+//! the model is always driven forwards, but nothing is written to the output
+//! until the stream is complete.
+//!
+//! Probabilities are internally rescaled to a power-of-two denominator (if
+//! they don't have one already), so that the decoder never needs to divide.
+//!
+//! # Example
+//!
+//! ```
+//! # use std::convert::Infallible;
+//! # use std::ops::Range;
+//! #
+//! use arithmetic_coding::{Model, ans};
+//!
+//! # #[derive(Clone)]
+//! # struct MyModel;
+//! #
+//! # impl Model for MyModel {
+//! #     type B = u32;
+//! #     type Symbol = u8;
+//! #     type ValueError = Infallible;
+//! #
+//! #     fn probability(&self, symbol: Option<&u8>) -> Result<Range<u32>, Infallible> {
+//! #         Ok(symbol.map_or(0..1, |&s| u32::from(s) + 1..u32::from(s) + 2))
+//! #     }
+//! #
+//! #     fn symbol(&self, value: u32) -> Option<u8> {
+//! #         value.checked_sub(1).map(|s| u8::try_from(s).unwrap())
+//! #     }
+//! #
+//! #     fn max_denominator(&self) -> u32 {
+//! #         257
+//! #     }
+//! # }
+//! let input = b"hello world".to_vec();
+//!
+//! let mut buffer = Vec::new();
+//! ans::Encoder::new(MyModel, &mut buffer)
+//!     .encode_all(input.iter().copied())
+//!     .unwrap();
+//!
+//! let mut decoder = ans::Decoder::new(MyModel, &buffer);
+//! let output: Vec<u8> = decoder.decode_all().map(Result::unwrap).collect();
+//!
+//! assert_eq!(input, output);
+//! ```
+
+use core::ops::Range;
+
+use crate::{BitStore, Model};
+
+pub mod decoder;
+pub mod encoder;
+
+pub use decoder::Decoder;
+pub use encoder::Encoder;
+
+/// The lower bound of the normalised state interval.
+///
+/// The state is kept in the range `[L, 256 * L)`, which leaves the most
+/// significant bit of the [`BitStore`] free.
+fn lower_bound<B: BitStore>() -> B {
+    B::ONE << (B::BITS - 9)
+}
+
+/// The number of bits used to represent probabilities internally.
+///
+/// This is the smallest power of two which is not less than the maximum
+/// denominator of the model.
+fn scale_bits<B: BitStore>(max_denominator: B) -> u32 {
+    if max_denominator > B::ONE {
+        (max_denominator - B::ONE).log2() + 1
+    } else {
+        0
+    }
+}
+
+/// Rescale a cumulative frequency from the model's denominator to a
+/// denominator of `2^scale_bits`.
+///
+/// Since the new denominator is never smaller than the old one, every symbol
+/// is still assigned a non-empty interval.
+fn quantise<B: BitStore>(cumulative_frequency: B, denominator: B, scale_bits: u32) -> B {
+    if denominator == B::ONE << scale_bits {
+        cumulative_frequency
+    } else {
+        (cumulative_frequency << scale_bits) / denominator
+    }
+}
+
+fn quantise_range<B: BitStore>(p: Range<B>, denominator: B, scale_bits: u32) -> Range<B> {
+    quantise(p.start, denominator, scale_bits)..quantise(p.end, denominator, scale_bits)
+}
+
+fn assert_precision_sufficient<M: Model>(max_denominator: M::B) {
+    let scale_bits = scale_bits(max_denominator);
+    assert!(
+        2 * scale_bits <= M::B::BITS,
+        "not enough bits in BitStore to support the required precision",
+    );
+}
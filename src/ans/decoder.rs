@@ -0,0 +1,220 @@
+//! The [`Decoder`] half of the rANS coder.
+
+use super::{assert_precision_sufficient, lower_bound, quantise_range, scale_bits};
+use crate::{BitStore, DecodeError, Model};
+
+/// An rANS decoder
+///
+/// An rANS decoder converts a stream of bytes into a stream of some output
+/// symbol, using a predictive [`Model`].
+#[derive(Debug)]
+pub struct Decoder<'a, M>
+where
+    M: Model,
+{
+    model: M,
+    scale_bits: u32,
+    state: State<'a, M::B>,
+}
+
+impl<'a, M> Decoder<'a, M>
+where
+    M: Model,
+{
+    /// Construct a new [`Decoder`]
+    ///
+    /// # Panics
+    ///
+    /// Probabilities are rescaled to a power-of-two denominator which is not
+    /// less than [`Model::max_denominator`]. The number of bits needed to
+    /// represent this denominator must be no more than half of
+    /// [`BitStore::BITS`].
+    ///
+    /// If this constraint cannot be satisfied this method will panic in debug
+    /// builds
+    pub fn new(model: M, input: &'a [u8]) -> Self {
+        let state = State::new(input);
+        Self::with_state(state, model)
+    }
+
+    /// Create a decoder from an existing [`State`] and [`Model`].
+    ///
+    /// This is useful for manually chaining a shared buffer through multiple
+    /// decoders.
+    pub fn with_state(state: State<'a, M::B>, model: M) -> Self {
+        #[cfg(debug_assertions)]
+        assert_precision_sufficient::<M>(model.max_denominator());
+        let scale_bits = scale_bits(model.max_denominator());
+
+        Self {
+            model,
+            scale_bits,
+            state,
+        }
+    }
+
+    /// Return an iterator over the decoded symbols.
+    ///
+    /// The iterator will continue returning symbols until EOF is reached
+    pub const fn decode_all(&mut self) -> DecodeIter<'_, 'a, M> {
+        DecodeIter { decoder: self }
+    }
+
+    /// Read the next symbol from the stream of bytes
+    ///
+    /// This method will return `Ok(None)` when EOF is reached.
+    ///
+    /// If the input runs out before EOF is reached, it is treated as though it
+    /// were padded with zeros.
+    ///
+    /// # Errors
+    ///
+    /// This method can fail if
+    /// - the [`Model`] rejects the decoded symbol
+    /// - the decoded symbol is inconsistent with the decoded value. This
+    ///   indicates that the stream is corrupt, or that the [`Model`] is
+    ///   implemented incorrectly.
+    /// - the state read from the start of the stream is too small to have been
+    ///   written by an [`Encoder`](super::Encoder), or it becomes zero once the
+    ///   input is exhausted. This happens if the input is empty or starts with
+    ///   zeros.
+    pub fn decode(&mut self) -> Result<Option<M::Symbol>, DecodeError<M::ValueError>> {
+        // the state only falls below the lower bound if it was read that way
+        // from the start of the stream
+        if self.state.x < lower_bound() {
+            return Err(DecodeError::CorruptStream);
+        }
+
+        let denominator = self.model.denominator();
+        debug_assert!(
+            denominator <= self.model.max_denominator(),
+            "denominator is greater than maximum!"
+        );
+        let value = self.state.value(denominator, self.scale_bits);
+        let (symbol, p) = self
+            .model
+            .decode_symbol(value)
+            .map_err(DecodeError::ValueError)?;
+
+        if !p.contains(&value) || p.end > denominator {
+            return Err(DecodeError::CorruptStream);
+        }
+
+        self.state.scale(
+            quantise_range(p, denominator, self.scale_bits),
+            self.scale_bits,
+        )?;
+        self.model.update(symbol.as_ref());
+
+        Ok(symbol)
+    }
+
+    /// Reuse the internal state of the Decoder with a new model.
+    ///
+    /// Allows for chaining multiple sequences of symbols from a single stream
+    /// of bytes
+    pub fn chain<X>(self, model: X) -> Decoder<'a, X>
+    where
+        X: Model<B = M::B>,
+    {
+        Decoder::with_state(self.state, model)
+    }
+
+    /// Return the internal model and state of the decoder.
+    pub fn into_inner(self) -> (M, State<'a, M::B>) {
+        (self.model, self.state)
+    }
+}
+
+/// The iterator returned by the [`Decoder::decode_all`] method
+#[allow(missing_debug_implementations)]
+pub struct DecodeIter<'d, 'a, M>
+where
+    M: Model,
+{
+    decoder: &'d mut Decoder<'a, M>,
+}
+
+impl<M> Iterator for DecodeIter<'_, '_, M>
+where
+    M: Model,
+{
+    type Item = Result<M::Symbol, DecodeError<M::ValueError>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.decoder.decode().transpose()
+    }
+}
+
+/// A convenience struct which stores the internal state of a [`Decoder`].
+#[derive(Debug)]
+pub struct State<'a, B>
+where
+    B: BitStore,
+{
+    x: B,
+    input: &'a [u8],
+}
+
+impl<'a, B> State<'a, B>
+where
+    B: BitStore,
+{
+    /// Create a new [`State`] from an input stream of bytes.
+    #[must_use]
+    pub fn new(input: &'a [u8]) -> Self {
+        let mut state = Self { x: B::ZERO, input };
+
+        for _ in 0..B::BITS / 8 {
+            state.shift_in();
+        }
+
+        state
+    }
+
+    const fn next_byte(&mut self) -> u8 {
+        match self.input.split_first() {
+            Some((&byte, rest)) => {
+                self.input = rest;
+                byte
+            }
+            None => 0,
+        }
+    }
+
+    fn shift_in(&mut self) {
+        self.x = (self.x << 8) + B::from(self.next_byte());
+    }
+
+    fn slot(&self, scale_bits: u32) -> B {
+        self.x & ((B::ONE << scale_bits) - B::ONE)
+    }
+
+    /// The value, on the scale of the model's denominator, which the current
+    /// slot falls into.
+    ///
+    /// This is the inverse of [`quantise`](super::quantise).
+    fn value(&self, denominator: B, scale_bits: u32) -> B {
+        let slot = self.slot(scale_bits);
+        if denominator == B::ONE << scale_bits {
+            slot
+        } else {
+            ((slot + B::ONE) * denominator - B::ONE) >> scale_bits
+        }
+    }
+
+    fn scale<E>(&mut self, p: core::ops::Range<B>, scale_bits: u32) -> Result<(), DecodeError<E>> {
+        let slot = self.slot(scale_bits);
+        self.x = (p.end - p.start) * (self.x >> scale_bits) + slot - p.start;
+
+        while self.x < lower_bound() {
+            // shifting in the zero padding would never change the state
+            if self.x == B::ZERO && self.input.is_empty() {
+                return Err(DecodeError::CorruptStream);
+            }
+            self.shift_in();
+        }
+
+        Ok(())
+    }
+}
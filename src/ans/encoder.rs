@@ -0,0 +1,199 @@
+//! The [`Encoder`] half of the rANS coder.
+
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use super::{assert_precision_sufficient, lower_bound, quantise_range, scale_bits};
+use crate::{BitStore, Model};
+
+/// An rANS encoder
+///
+/// An rANS encoder converts a stream of symbols into a stream of bytes, using
+/// a predictive [`Model`].
+///
+/// Since rANS encodes symbols in reverse order, the encoder buffers the
+/// (rescaled) probability of each symbol, and nothing is written to the output
+/// until [`Encoder::flush`] is called.
+#[derive(Debug)]
+pub struct Encoder<'a, M>
+where
+    M: Model,
+{
+    model: M,
+    scale_bits: u32,
+    state: State<'a, M::B>,
+}
+
+impl<'a, M> Encoder<'a, M>
+where
+    M: Model,
+{
+    /// Construct a new [`Encoder`].
+    ///
+    /// Encoded bytes are appended to the given output buffer when the encoder
+    /// is flushed.
+    ///
+    /// # Panics
+    ///
+    /// Probabilities are rescaled to a power-of-two denominator which is not
+    /// less than [`Model::max_denominator`]. The number of bits needed to
+    /// represent this denominator must be no more than half of
+    /// [`BitStore::BITS`].
+    ///
+    /// If this constraint cannot be satisfied this method will panic in debug
+    /// builds
+    pub fn new(model: M, output: &'a mut Vec<u8>) -> Self {
+        let state = State::new(output);
+        Self::with_state(state, model)
+    }
+
+    /// Create an encoder from an existing [`State`].
+    ///
+    /// This is useful for manually chaining a shared buffer through multiple
+    /// encoders.
+    pub fn with_state(state: State<'a, M::B>, model: M) -> Self {
+        #[cfg(debug_assertions)]
+        assert_precision_sufficient::<M>(model.max_denominator());
+        let scale_bits = scale_bits(model.max_denominator());
+        Self {
+            model,
+            scale_bits,
+            state,
+        }
+    }
+
+    /// Encode a stream of symbols into the provided output.
+    ///
+    /// This method will encode all the symbols in the iterator, followed by EOF
+    /// (`None`), and then call [`Encoder::flush`].
+    ///
+    /// # Errors
+    ///
+    /// This method can fail if the [`Model`] rejects a symbol.
+    pub fn encode_all(
+        mut self,
+        symbols: impl IntoIterator<Item = M::Symbol>,
+    ) -> Result<(), M::ValueError> {
+        for symbol in symbols {
+            self.encode(Some(&symbol))?;
+        }
+        self.encode(None)?;
+        self.flush();
+        Ok(())
+    }
+
+    /// Encode a symbol into the provided output.
+    ///
+    /// When you finish encoding symbols, you must manually encode an EOF symbol
+    /// by calling [`Encoder::encode`] with `None`.
+    ///
+    /// The internal buffer must be manually flushed using [`Encoder::flush`].
+    ///
+    /// # Errors
+    ///
+    /// This method can fail if the [`Model`] rejects a symbol.
+    pub fn encode(&mut self, symbol: Option<&M::Symbol>) -> Result<(), M::ValueError> {
+        let p = self.model.probability(symbol)?;
+        let denominator = self.model.denominator();
+        debug_assert!(
+            denominator <= self.model.max_denominator(),
+            "denominator is greater than maximum!"
+        );
+
+        self.state.push(
+            quantise_range(p, denominator, self.scale_bits),
+            self.scale_bits,
+        );
+        self.model.update(symbol);
+
+        Ok(())
+    }
+
+    /// Encode all buffered symbols, and write them to the output.
+    ///
+    /// This method must be called when you finish writing symbols to a stream
+    /// of bytes. This is called automatically when you use
+    /// [`Encoder::encode_all`].
+    pub fn flush(self) {
+        self.state.flush();
+    }
+
+    /// Return the internal model and state of the encoder.
+    pub fn into_inner(self) -> (M, State<'a, M::B>) {
+        (self.model, self.state)
+    }
+
+    /// Reuse the internal state of the Encoder with a new model.
+    ///
+    /// Allows for chaining multiple sequences of symbols into a single stream
+    /// of bytes
+    pub fn chain<X>(self, model: X) -> Encoder<'a, X>
+    where
+        X: Model<B = M::B>,
+    {
+        Encoder::with_state(self.state, model)
+    }
+}
+
+/// A convenience struct which stores the internal state of an [`Encoder`].
+#[derive(Debug)]
+pub struct State<'a, B>
+where
+    B: BitStore,
+{
+    /// The rescaled probability of each symbol, along with the scale it was
+    /// rescaled to.
+    intervals: Vec<(Range<B>, u32)>,
+    output: &'a mut Vec<u8>,
+}
+
+impl<'a, B> State<'a, B>
+where
+    B: BitStore,
+{
+    /// Manually construct a [`State`].
+    ///
+    /// Normally this would be done automatically using the [`Encoder::new`]
+    /// method.
+    pub const fn new(output: &'a mut Vec<u8>) -> Self {
+        Self {
+            intervals: Vec::new(),
+            output,
+        }
+    }
+
+    fn push(&mut self, p: Range<B>, scale_bits: u32) {
+        self.intervals.push((p, scale_bits));
+    }
+
+    /// Encode all the buffered symbols, and write the result to the output.
+    ///
+    /// This method MUST be called when you finish writing symbols to ensure
+    /// they are written to the output.
+    pub fn flush(self) {
+        // bytes are produced in the reverse of the order in which the decoder
+        // consumes them
+        let mut bytes = Vec::new();
+        let mut x = lower_bound::<B>();
+
+        for (p, scale_bits) in self.intervals.into_iter().rev() {
+            let frequency = p.end - p.start;
+            let x_max = ((lower_bound::<B>() >> scale_bits) << 8) * frequency;
+            while x >= x_max {
+                bytes.push(x.low_byte());
+                x = x >> 8;
+            }
+
+            let quotient = x / frequency;
+            let remainder = x - quotient * frequency;
+            x = (quotient << scale_bits) + remainder + p.start;
+        }
+
+        for _ in 0..B::BITS / 8 {
+            bytes.push(x.low_byte());
+            x = x >> 8;
+        }
+
+        self.output.extend(bytes.into_iter().rev());
+    }
+}
@@ -0,0 +1,224 @@
+//! An adaptive binary arithmetic coder.
+//!
+//! Many models are really a sequence of binary decisions. Rather than going
+//! through the general-purpose [`Model`](crate::Model) interface with a
+//! two-symbol alphabet (plus EOF), this module provides a dedicated binary
+//! coder in the style of LZMA and CABAC.
+//!
+//! Each decision is coded against an adaptive probability counter, [`Prob`],
+//! which is updated after each bit using a simple shift. Bits which are (close
+//! to) equiprobable can skip the counter entirely and be coded using the
+//! 'bypass' path.
+//!
+//! The binary coder is built on top of the byte-oriented
+//! [`range_coder`](crate::range_coder), and can be mixed freely with
+//! model-based coding over the same stream (see [`Encoder::into_inner`] and
+//! [`Encoder::with_state`]).
+//!
+//! # Example
+//!
+//! ```
+//! use arithmetic_coding::binary::{Decoder, Encoder, Prob};
+//!
+//! let input = [true, true, false, true, true, true, false, true];
+//!
+//! let mut buffer = Vec::new();
+//! let mut encoder = Encoder::new(&mut buffer);
+//! let mut prob = Prob::<11, 5>::new();
+//! for &bit in &input {
+//!     encoder.encode_bit(&mut prob, bit);
+//! }
+//! encoder.encode_bypass(true);
+//! encoder.flush();
+//!
+//! let mut decoder = Decoder::new(&buffer);
+//! let mut prob = Prob::<11, 5>::new();
+//! for &bit in &input {
+//!     assert_eq!(decoder.decode_bit(&mut prob), bit);
+//! }
+//! assert!(decoder.decode_bypass());
+//! ```
+
+use alloc::vec::Vec;
+
+use crate::range_coder::{decoder, encoder};
+
+/// An adaptive estimate of the probability that a binary decision is `false`.
+///
+/// The probability is stored as a fixed-point number with `BITS` bits of
+/// precision (typically 11 or 12). After each decision, the estimate moves
+/// towards the observed value by a fraction `1 / 2^SHIFT` of the remaining
+/// distance. Smaller values of `SHIFT` adapt more quickly, larger values give
+/// a more stable estimate.
+///
+/// The estimate never reaches 0 or 1, so both outcomes can always be coded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Prob<const BITS: u32 = 11, const SHIFT: u32 = 5> {
+    p: u16,
+}
+
+impl<const BITS: u32, const SHIFT: u32> Prob<BITS, SHIFT> {
+    /// Create a new counter, with both outcomes equally probable.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self::with_probability(1 << (BITS - 1))
+    }
+
+    /// Create a new counter with an initial probability that the decision is
+    /// `false`, on a scale of `2^BITS`.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the probability is not strictly between 0 and
+    /// `2^BITS`.
+    #[must_use]
+    pub const fn with_probability(p: u16) -> Self {
+        const {
+            assert!(BITS > 1 && BITS <= 15, "BITS must be between 2 and 15");
+            assert!(
+                SHIFT > 0 && SHIFT < BITS,
+                "SHIFT must be between 1 and BITS"
+            );
+        }
+        assert!(
+            p > 0 && (p as u32) < (1 << BITS),
+            "probability out of range"
+        );
+        Self { p }
+    }
+
+    /// The current probability that the decision is `false`, on a scale of
+    /// `2^BITS`.
+    #[must_use]
+    pub const fn get(self) -> u16 {
+        self.p
+    }
+
+    /// Update the estimate with the latest decision.
+    pub const fn update(&mut self, bit: bool) {
+        if bit {
+            self.p -= self.p >> SHIFT;
+        } else {
+            self.p += ((1 << BITS) - self.p) >> SHIFT;
+        }
+    }
+
+    fn bound(self, range: u64) -> u64 {
+        (range >> BITS) * u64::from(self.p)
+    }
+}
+
+impl<const BITS: u32, const SHIFT: u32> Default for Prob<BITS, SHIFT> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// An adaptive binary arithmetic encoder
+#[derive(Debug)]
+pub struct Encoder<'a> {
+    state: encoder::State<'a, u64>,
+}
+
+impl<'a> Encoder<'a> {
+    /// Construct a new [`Encoder`].
+    ///
+    /// Encoded bytes are appended to the given output buffer.
+    pub fn new(output: &'a mut Vec<u8>) -> Self {
+        Self::with_state(encoder::State::new(output))
+    }
+
+    /// Create an encoder from an existing range coder
+    /// [`State`](encoder::State).
+    ///
+    /// This allows binary decisions to be interleaved with symbols encoded
+    /// using a [`range_coder::Encoder`](crate::range_coder::Encoder).
+    #[must_use]
+    pub const fn with_state(state: encoder::State<'a, u64>) -> Self {
+        Self { state }
+    }
+
+    /// Encode a binary decision using an adaptive probability, and update the
+    /// probability.
+    pub fn encode_bit<const BITS: u32, const SHIFT: u32>(
+        &mut self,
+        prob: &mut Prob<BITS, SHIFT>,
+        bit: bool,
+    ) {
+        let bound = prob.bound(self.state.range());
+        self.state.encode_bit(bound, bit);
+        prob.update(bit);
+    }
+
+    /// Encode an equiprobable binary decision, bypassing the probability
+    /// model.
+    pub fn encode_bypass(&mut self, bit: bool) {
+        let bound = self.state.range() >> 1;
+        self.state.encode_bit(bound, bit);
+    }
+
+    /// Flush any pending bytes from the buffer
+    ///
+    /// This method must be called when you finish writing bits to a stream
+    /// of bytes.
+    pub fn flush(self) {
+        self.state.flush();
+    }
+
+    /// Return the internal state of the encoder.
+    #[must_use]
+    pub const fn into_inner(self) -> encoder::State<'a, u64> {
+        self.state
+    }
+}
+
+/// An adaptive binary arithmetic decoder
+#[derive(Debug)]
+pub struct Decoder<'a> {
+    state: decoder::State<'a, u64>,
+}
+
+impl<'a> Decoder<'a> {
+    /// Construct a new [`Decoder`].
+    ///
+    /// If the input runs out before decoding is finished, it is treated as
+    /// though it were padded with zeros.
+    #[must_use]
+    pub fn new(input: &'a [u8]) -> Self {
+        Self::with_state(decoder::State::new(input))
+    }
+
+    /// Create a decoder from an existing range coder [`State`](decoder::State).
+    ///
+    /// This allows binary decisions to be interleaved with symbols decoded
+    /// using a [`range_coder::Decoder`](crate::range_coder::Decoder).
+    #[must_use]
+    pub const fn with_state(state: decoder::State<'a, u64>) -> Self {
+        Self { state }
+    }
+
+    /// Decode a binary decision using an adaptive probability, and update the
+    /// probability.
+    pub fn decode_bit<const BITS: u32, const SHIFT: u32>(
+        &mut self,
+        prob: &mut Prob<BITS, SHIFT>,
+    ) -> bool {
+        let bound = prob.bound(self.state.range());
+        let bit = self.state.decode_bit(bound);
+        prob.update(bit);
+        bit
+    }
+
+    /// Decode an equiprobable binary decision, bypassing the probability
+    /// model.
+    pub fn decode_bypass(&mut self) -> bool {
+        let bound = self.state.range() >> 1;
+        self.state.decode_bit(bound)
+    }
+
+    /// Return the internal state of the decoder.
+    #[must_use]
+    pub const fn into_inner(self) -> decoder::State<'a, u64> {
+        self.state
+    }
+}
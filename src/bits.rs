@@ -0,0 +1,632 @@
+//! Sources and sinks of bits for the [`Encoder`](crate::Encoder) and
+//! [`Decoder`](crate::Decoder).
+//!
+//! With the `std` feature, any [`bitstream_io::BitWrite`] or
+//! [`bitstream_io::BitRead`] can be used directly. For in-memory buffers, the
+//! [`VecWriter`], [`SliceWriter`], and [`SliceReader`] types avoid the overhead
+//! of a general purpose bit stream by accumulating bits in a machine word. The
+//! [`BitCounter`] only counts the bits written to it.
+//!
+//! Bits are packed most significant bit first, so the output is identical to
+//! that of a big-endian [`bitstream_io::BitWriter`].
+//!
+//! ```
+//! use arithmetic_coding::{
+//!     Decoder, Encoder,
+//!     bits::{SliceReader, VecWriter},
+//!     fixed_length,
+//! };
+//! # use std::ops::Range;
+//! #
+//! # struct Bytes;
+//! #
+//! # impl fixed_length::Model for Bytes {
+//! #     type B = u32;
+//! #     type Symbol = u8;
+//! #     type ValueError = std::convert::Infallible;
+//! #
+//! #     fn probability(&self, symbol: &u8) -> Result<Range<u32>, Self::ValueError> {
+//! #         Ok(u32::from(*symbol)..u32::from(*symbol) + 1)
+//! #     }
+//! #
+//! #     fn symbol(&self, value: u32) -> u8 {
+//! #         u8::try_from(value).unwrap()
+//! #     }
+//! #
+//! #     fn max_denominator(&self) -> u32 {
+//! #         256
+//! #     }
+//! #
+//! #     fn length(&self) -> usize {
+//! #         5
+//! #     }
+//! # }
+//!
+//! let input = b"hello";
+//!
+//! let mut writer = VecWriter::new();
+//! Encoder::new(fixed_length::Wrapper::new(Bytes), &mut writer)
+//!     .encode_all(input.iter().copied())
+//!     .unwrap();
+//! let buffer = writer.finish();
+//!
+//! let mut decoder = Decoder::new(fixed_length::Wrapper::new(Bytes), SliceReader::new(&buffer));
+//! let output: Vec<u8> = decoder.decode_all().map(Result::unwrap).collect();
+//!
+//! assert_eq!(output, input);
+//! ```
+
+use alloc::vec::Vec;
+
+#[cfg(feature = "std")]
+use bitstream_io::{BitRead, BitReader, BitWrite, Endianness};
+
+const WORD_BITS: u32 = u64::BITS;
+const WORD_BYTES: usize = WORD_BITS as usize / 8;
+
+pub mod io {
+    //! The error type of [`BitSink`](super::BitSink) and
+    //! [`BitSource`](super::BitSource).
+    //!
+    //! With the `std` feature, these are re-exported from [`std::io`].
+    //! Otherwise, they are minimal stand-ins with the same names.
+
+    #[cfg(feature = "std")]
+    pub use std::io::{Error, ErrorKind, Result};
+
+    #[cfg(not(feature = "std"))]
+    pub use self::no_std::{Error, ErrorKind, Result};
+
+    #[cfg(not(feature = "std"))]
+    mod no_std {
+        use core::fmt;
+
+        /// A specialized [`Result`](core::result::Result) type for bit I/O.
+        pub type Result<T> = core::result::Result<T, Error>;
+
+        /// A list specifying general categories of bit I/O error.
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        #[non_exhaustive]
+        pub enum ErrorKind {
+            /// A parameter was incorrect
+            InvalidInput,
+
+            /// Data not valid for the operation were encountered
+            InvalidData,
+
+            /// The input ended prematurely
+            UnexpectedEof,
+
+            /// The output could not be written to
+            WriteZero,
+
+            /// Any other error
+            Other,
+        }
+
+        impl fmt::Display for ErrorKind {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                let description = match self {
+                    Self::InvalidInput => "invalid input parameter",
+                    Self::InvalidData => "invalid data",
+                    Self::UnexpectedEof => "unexpected end of file",
+                    Self::WriteZero => "write zero",
+                    Self::Other => "other error",
+                };
+                f.write_str(description)
+            }
+        }
+
+        /// The error type for bit I/O operations.
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        pub struct Error {
+            kind: ErrorKind,
+            message: Option<&'static str>,
+        }
+
+        impl Error {
+            /// Create a new error from a kind and a message.
+            #[must_use]
+            pub const fn new(kind: ErrorKind, message: &'static str) -> Self {
+                Self {
+                    kind,
+                    message: Some(message),
+                }
+            }
+
+            /// The general category of this error.
+            #[must_use]
+            pub const fn kind(&self) -> ErrorKind {
+                self.kind
+            }
+        }
+
+        impl From<ErrorKind> for Error {
+            fn from(kind: ErrorKind) -> Self {
+                Self {
+                    kind,
+                    message: None,
+                }
+            }
+        }
+
+        impl fmt::Display for Error {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                match self.message {
+                    Some(message) => f.write_str(message),
+                    None => self.kind.fmt(f),
+                }
+            }
+        }
+
+        impl core::error::Error for Error {}
+    }
+}
+
+/// A destination for the bits produced by an [`Encoder`](crate::Encoder).
+pub trait BitSink {
+    /// Write a single bit.
+    ///
+    /// # Errors
+    ///
+    /// This method can fail if the underlying output cannot be written to.
+    fn put_bit(&mut self, bit: bool) -> io::Result<()>;
+}
+
+/// A source of bits for a [`Decoder`](crate::Decoder).
+pub trait BitSource {
+    /// Read a single bit, or `None` if the end of the input has been reached.
+    ///
+    /// # Errors
+    ///
+    /// This method can fail if the underlying input cannot be read from.
+    fn next_bit(&mut self) -> io::Result<Option<bool>>;
+}
+
+/// A [`BitSource`] which can be moved backwards.
+///
+/// This is required by [`Decoder::finish`](crate::Decoder::finish) to return
+/// the bits which the decoder read past the end of the stream.
+pub trait Rewind: BitSource {
+    /// Move the read position back by the given number of bits.
+    ///
+    /// # Errors
+    ///
+    /// This method can fail if the underlying input cannot be rewound.
+    fn rewind(&mut self, bits: u64) -> io::Result<()>;
+}
+
+/// A [`BitSink`] which can discard bits that have already been written.
+///
+/// This is required by [`Encoder::rollback`](crate::Encoder::rollback).
+pub trait Truncate: BitSink {
+    /// The number of bits written so far.
+    fn position(&self) -> u64;
+
+    /// Discard all the bits written after the given position.
+    ///
+    /// # Errors
+    ///
+    /// This method can fail if the position is beyond the bits written so
+    /// far, or if the underlying output cannot be truncated.
+    fn truncate(&mut self, position: u64) -> io::Result<()>;
+}
+
+#[cfg(feature = "std")]
+impl<T> BitSink for T
+where
+    T: BitWrite,
+{
+    fn put_bit(&mut self, bit: bool) -> io::Result<()> {
+        self.write_bit(bit)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> BitSource for T
+where
+    T: BitRead,
+{
+    fn next_bit(&mut self) -> io::Result<Option<bool>> {
+        match self.read_bit() {
+            Ok(bit) => Ok(Some(bit)),
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R, E> Rewind for BitReader<R, E>
+where
+    R: std::io::Read + std::io::Seek,
+    E: Endianness,
+{
+    fn rewind(&mut self, bits: u64) -> io::Result<()> {
+        let bits = i64::try_from(bits).map_err(|_| io::ErrorKind::InvalidInput)?;
+        self.seek_bits(std::io::SeekFrom::Current(-bits))?;
+        Ok(())
+    }
+}
+
+// the blanket implementations above cover `&mut` references to bitstream-io
+// types, so references to the types in this module need their own
+// implementations
+macro_rules! impl_by_ref {
+    (BitSink for $t:ty) => {
+        impl BitSink for &mut $t {
+            fn put_bit(&mut self, bit: bool) -> io::Result<()> {
+                (**self).put_bit(bit)
+            }
+        }
+    };
+    (Truncate for $t:ty) => {
+        impl Truncate for &mut $t {
+            fn position(&self) -> u64 {
+                (**self).position()
+            }
+
+            fn truncate(&mut self, position: u64) -> io::Result<()> {
+                (**self).truncate(position)
+            }
+        }
+    };
+    (BitSource for $t:ty) => {
+        impl BitSource for &mut $t {
+            fn next_bit(&mut self) -> io::Result<Option<bool>> {
+                (**self).next_bit()
+            }
+        }
+    };
+}
+
+impl_by_ref!(BitSink for VecWriter);
+impl_by_ref!(BitSink for SliceWriter<'_>);
+impl_by_ref!(BitSink for BitCounter);
+impl_by_ref!(Truncate for VecWriter);
+impl_by_ref!(Truncate for BitCounter);
+impl_by_ref!(Truncate for SliceWriter<'_>);
+impl_by_ref!(BitSource for SliceReader<'_>);
+
+/// A machine word of buffered output bits.
+#[derive(Debug, Default, Clone, Copy)]
+struct Word {
+    bits: u64,
+    len: u32,
+}
+
+impl Word {
+    /// Append a bit, returning the full word once [`WORD_BITS`] bits have
+    /// been accumulated.
+    const fn push(&mut self, bit: bool) -> Option<[u8; WORD_BYTES]> {
+        self.bits = (self.bits << 1) | bit as u64;
+        self.len += 1;
+        if self.len == WORD_BITS {
+            let bytes = self.bits.to_be_bytes();
+            *self = Self { bits: 0, len: 0 };
+            Some(bytes)
+        } else {
+            None
+        }
+    }
+
+    /// The remaining bits, padded with zeros to a whole number of bytes.
+    const fn tail(self) -> ([u8; WORD_BYTES], usize) {
+        let bytes = if self.len == 0 {
+            [0; WORD_BYTES]
+        } else {
+            (self.bits << (WORD_BITS - self.len)).to_be_bytes()
+        };
+        (bytes, self.len.div_ceil(8) as usize)
+    }
+
+    /// The number of bits written, given the bytes which have already been
+    /// flushed from this word.
+    const fn position(self, written: &[u8]) -> u64 {
+        written.len() as u64 * 8 + self.len as u64
+    }
+
+    /// Discard the bits after the given position, given the bytes which have
+    /// already been flushed from this word.
+    ///
+    /// Returns the number of flushed bytes which should be kept. Any bits of a
+    /// partially kept byte are moved back into the word.
+    fn truncate(&mut self, written: &[u8], position: u64) -> io::Result<usize> {
+        if position > self.position(written) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "cannot truncate beyond the end of the output",
+            ));
+        }
+
+        let flushed = written.len() as u64 * 8;
+        #[allow(clippy::cast_possible_truncation)]
+        if let Some(keep) = position.checked_sub(flushed) {
+            let keep = keep as u32;
+            self.bits = self.bits.checked_shr(self.len - keep).unwrap_or(0);
+            self.len = keep;
+            Ok(written.len())
+        } else {
+            let bytes = (position / 8) as usize;
+            let len = (position % 8) as u32;
+            let bits = if len == 0 {
+                0
+            } else {
+                u64::from(written[bytes] >> (8 - len))
+            };
+            *self = Self { bits, len };
+            Ok(bytes)
+        }
+    }
+}
+
+/// A [`BitSink`] which writes to a growable [`Vec`].
+#[derive(Debug, Default)]
+pub struct VecWriter {
+    output: Vec<u8>,
+    word: Word,
+}
+
+impl VecWriter {
+    /// Create a new, empty [`VecWriter`].
+    #[must_use]
+    pub const fn new() -> Self {
+        Self::with_buffer(Vec::new())
+    }
+
+    /// Create a [`VecWriter`] which appends to an existing buffer.
+    #[must_use]
+    pub const fn with_buffer(output: Vec<u8>) -> Self {
+        Self {
+            output,
+            word: Word { bits: 0, len: 0 },
+        }
+    }
+
+    /// Write any buffered bits, padded with zeros to a whole number of bytes,
+    /// and return the output buffer.
+    #[must_use]
+    pub fn finish(mut self) -> Vec<u8> {
+        let (bytes, len) = self.word.tail();
+        self.output.extend_from_slice(&bytes[..len]);
+        self.output
+    }
+}
+
+impl BitSink for VecWriter {
+    fn put_bit(&mut self, bit: bool) -> io::Result<()> {
+        if let Some(bytes) = self.word.push(bit) {
+            self.output.extend_from_slice(&bytes);
+        }
+        Ok(())
+    }
+}
+
+impl Truncate for VecWriter {
+    fn position(&self) -> u64 {
+        self.word.position(&self.output)
+    }
+
+    fn truncate(&mut self, position: u64) -> io::Result<()> {
+        let len = self.word.truncate(&self.output, position)?;
+        self.output.truncate(len);
+        Ok(())
+    }
+}
+
+/// A [`BitSink`] which counts the bits written to it, and discards them.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct BitCounter {
+    bits: u64,
+}
+
+impl BitCounter {
+    /// Create a new [`BitCounter`].
+    #[must_use]
+    pub const fn new() -> Self {
+        Self { bits: 0 }
+    }
+
+    /// The number of bits written so far.
+    #[must_use]
+    pub const fn bits(self) -> u64 {
+        self.bits
+    }
+}
+
+impl BitSink for BitCounter {
+    fn put_bit(&mut self, _bit: bool) -> io::Result<()> {
+        self.bits += 1;
+        Ok(())
+    }
+}
+
+impl Truncate for BitCounter {
+    fn position(&self) -> u64 {
+        self.bits
+    }
+
+    fn truncate(&mut self, position: u64) -> io::Result<()> {
+        if position > self.bits {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "cannot truncate beyond the end of the output",
+            ));
+        }
+        self.bits = position;
+        Ok(())
+    }
+}
+
+/// The error returned when a [`SliceWriter`] runs out of space.
+#[derive(Debug, Clone, Copy, thiserror::Error)]
+#[error("output buffer is full (capacity: {capacity} bytes)")]
+pub struct CapacityError {
+    /// The capacity of the output buffer in bytes
+    pub capacity: usize,
+}
+
+impl From<CapacityError> for io::Error {
+    #[cfg(feature = "std")]
+    fn from(e: CapacityError) -> Self {
+        Self::new(io::ErrorKind::WriteZero, e)
+    }
+
+    #[cfg(not(feature = "std"))]
+    fn from(_: CapacityError) -> Self {
+        Self::new(io::ErrorKind::WriteZero, "output buffer is full")
+    }
+}
+
+/// A [`BitSink`] which writes to a fixed-size slice.
+///
+/// If the output does not fit in the slice, writing fails with a
+/// [`CapacityError`] (wrapped in an [`io::Error`]).
+#[derive(Debug)]
+pub struct SliceWriter<'a> {
+    output: &'a mut [u8],
+    position: usize,
+    word: Word,
+}
+
+impl<'a> SliceWriter<'a> {
+    /// Create a new [`SliceWriter`] which writes to the start of the given
+    /// slice.
+    #[must_use]
+    pub const fn new(output: &'a mut [u8]) -> Self {
+        Self {
+            output,
+            position: 0,
+            word: Word { bits: 0, len: 0 },
+        }
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> Result<(), CapacityError> {
+        let capacity = self.output.len();
+        let end = self.position + bytes.len();
+        self.output
+            .get_mut(self.position..end)
+            .ok_or(CapacityError { capacity })?
+            .copy_from_slice(bytes);
+        self.position = end;
+        Ok(())
+    }
+
+    /// Write any buffered bits, padded with zeros to a whole number of bytes,
+    /// and return the number of bytes written.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`CapacityError`] if the remaining bits do not fit in the
+    /// slice.
+    pub fn finish(mut self) -> Result<usize, CapacityError> {
+        let (bytes, len) = self.word.tail();
+        self.write(&bytes[..len])?;
+        Ok(self.position)
+    }
+}
+
+impl BitSink for SliceWriter<'_> {
+    fn put_bit(&mut self, bit: bool) -> io::Result<()> {
+        if let Some(bytes) = self.word.push(bit) {
+            self.write(&bytes)?;
+        }
+        Ok(())
+    }
+}
+
+impl Truncate for SliceWriter<'_> {
+    fn position(&self) -> u64 {
+        self.word.position(&self.output[..self.position])
+    }
+
+    fn truncate(&mut self, position: u64) -> io::Result<()> {
+        self.position = self
+            .word
+            .truncate(&self.output[..self.position], position)?;
+        Ok(())
+    }
+}
+
+/// A [`BitSource`] which reads from a slice.
+#[derive(Debug, Clone)]
+pub struct SliceReader<'a> {
+    input: &'a [u8],
+    position: usize,
+    bits: u64,
+    len: u32,
+}
+
+impl<'a> SliceReader<'a> {
+    /// Create a new [`SliceReader`] which reads from the start of the given
+    /// slice.
+    #[must_use]
+    pub const fn new(input: &'a [u8]) -> Self {
+        Self {
+            input,
+            position: 0,
+            bits: 0,
+            len: 0,
+        }
+    }
+
+    /// The number of bits read so far.
+    #[must_use]
+    pub const fn position(&self) -> u64 {
+        self.position as u64 * 8 - self.len as u64
+    }
+
+    /// Move to the given bit position, counted from the start of the slice.
+    ///
+    /// Seeking beyond the end of the slice leaves the reader at the end.
+    pub fn seek(&mut self, position: u64) {
+        let byte = usize::try_from(position / 8)
+            .map_or(self.input.len(), |byte| byte.min(self.input.len()));
+
+        self.load(byte);
+        let skip = ((position % 8) as u32).min(self.len);
+        self.bits <<= skip;
+        self.len -= skip;
+    }
+
+    /// Load up to a word of bits starting at the given byte.
+    fn load(&mut self, position: usize) {
+        let remaining = &self.input[position.min(self.input.len())..];
+        let n = remaining.len().min(WORD_BYTES);
+        let mut bytes = [0; WORD_BYTES];
+        bytes[..n].copy_from_slice(&remaining[..n]);
+
+        self.bits = u64::from_be_bytes(bytes);
+        #[allow(clippy::cast_possible_truncation)]
+        let len = n as u32 * 8;
+        self.len = len;
+        self.position = position + n;
+    }
+}
+
+impl BitSource for SliceReader<'_> {
+    fn next_bit(&mut self) -> io::Result<Option<bool>> {
+        if self.len == 0 {
+            self.load(self.position);
+            if self.len == 0 {
+                return Ok(None);
+            }
+        }
+
+        let bit = self.bits >> (WORD_BITS - 1) == 1;
+        self.bits <<= 1;
+        self.len -= 1;
+        Ok(Some(bit))
+    }
+}
+
+impl Rewind for SliceReader<'_> {
+    fn rewind(&mut self, bits: u64) -> io::Result<()> {
+        let target = self
+            .position()
+            .checked_sub(bits)
+            .ok_or(io::ErrorKind::InvalidInput)?;
+        self.seek(target);
+        Ok(())
+    }
+}
@@ -1,14 +1,16 @@
-use std::ops::Range;
+use core::ops::Range;
 
-use arithmetic_coding_core::BitStore;
+use arithmetic_coding_core::{BitStore, Reciprocal};
 
-use crate::Model;
+use crate::{InvalidProbabilityKind, Model, PrecisionError, Termination};
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct State<B: BitStore> {
     pub precision: u32,
     pub low: B,
     pub high: B,
+    /// The reciprocal of the last denominator which wasn't a power of two
+    reciprocal: Option<Reciprocal<B>>,
 }
 
 impl<B> State<B>
@@ -23,6 +25,7 @@ where
             precision,
             low,
             high,
+            reciprocal: None,
         }
     }
 
@@ -38,22 +41,156 @@ where
         self.half() + self.quarter()
     }
 
-    pub fn scale(&mut self, p: Range<B>, denominator: B) {
+    /// Narrow the interval to the given probability range.
+    ///
+    /// If the denominator is a power of two (see [`Model::denominator_log2`])
+    /// this uses shifts, otherwise it divides by a cached reciprocal of the
+    /// denominator.
+    ///
+    /// If the range is too small to be represented at the current precision,
+    /// the state is left unchanged and an error is returned.
+    pub fn scale(
+        &mut self,
+        p: Range<B>,
+        denominator: B,
+        denominator_log2: Option<u32>,
+    ) -> Result<(), IntervalCollapsed> {
         let range = self.high - self.low + B::ONE;
+        let (lower, upper) = denominator_log2.map_or_else(
+            || {
+                let reciprocal = self.reciprocal(denominator);
+                (
+                    range.mul_div_reciprocal(p.start, reciprocal),
+                    range.mul_div_reciprocal(p.end, reciprocal),
+                )
+            },
+            |shift| (range.mul_shr(p.start, shift), range.mul_shr(p.end, shift)),
+        );
+
+        if upper <= lower {
+            return Err(IntervalCollapsed);
+        }
+
+        self.high = self.low + upper - B::ONE;
+        self.low += lower;
+        Ok(())
+    }
+
+    /// The reciprocal of the denominator, which is only recalculated when the
+    /// denominator changes.
+    fn reciprocal(&mut self, denominator: B) -> Reciprocal<B> {
+        match self.reciprocal {
+            Some(reciprocal) if reciprocal.divisor() == denominator => reciprocal,
+            _ => *self.reciprocal.insert(denominator.reciprocal()),
+        }
+    }
 
-        self.high = self.low + (range * p.end) / denominator - B::ONE;
-        self.low += (range * p.start) / denominator;
+    /// Select the bits which terminate the stream in this state.
+    ///
+    /// The terminating bits must identify a sub-interval of the current
+    /// interval, regardless of any bits which follow them. Returns the first
+    /// bit and the number of bits. Any subsequent bits are the complement of
+    /// the first.
+    pub fn tail(&self, termination: Termination, pending: u32) -> (bool, u32) {
+        let max = (B::ONE << self.precision) - B::ONE;
+        match termination {
+            Termination::Minimal if pending == 0 && self.low == B::ZERO && self.high == max => {
+                (false, 0)
+            }
+            Termination::Minimal if self.low == B::ZERO => (false, 1),
+            Termination::Minimal if self.high == max => (true, 1),
+            _ => (self.low > self.quarter(), 2),
+        }
     }
 }
 
-pub fn assert_precision_sufficient<M: Model>(max_denominator: M::B, precision: u32) {
+/// Check that the denominator of a model is valid.
+pub fn check_denominator<B: BitStore>(
+    denominator: B,
+    denominator_log2: Option<u32>,
+    max_denominator: B,
+) -> Result<(), InvalidProbabilityKind> {
+    if denominator == B::ZERO {
+        Err(InvalidProbabilityKind::ZeroDenominator)
+    } else if denominator > max_denominator {
+        Err(InvalidProbabilityKind::DenominatorTooLarge)
+    } else if denominator_log2.is_some_and(|n| n >= B::BITS || B::ONE << n != denominator) {
+        Err(InvalidProbabilityKind::WrongDenominatorLog2)
+    } else {
+        Ok(())
+    }
+}
+
+/// Check that a probability range returned by a model is valid.
+pub fn check_probability<B: BitStore>(
+    p: &Range<B>,
+    denominator: B,
+    denominator_log2: Option<u32>,
+    max_denominator: B,
+) -> Result<(), InvalidProbabilityKind> {
+    check_denominator(denominator, denominator_log2, max_denominator)?;
+    if p.start >= p.end {
+        Err(InvalidProbabilityKind::EmptyRange)
+    } else if p.end > denominator {
+        Err(InvalidProbabilityKind::OutOfBounds)
+    } else {
+        Ok(())
+    }
+}
+
+/// The coding interval has collapsed to nothing
+#[derive(Debug)]
+pub struct IntervalCollapsed;
+
+/// The precision used by default, which is the number of bits of the
+/// [`BitStore`] not needed to represent the denominator.
+///
+/// Changing this would change the encoded output of every existing model, so
+/// it doesn't take advantage of the wider intermediate products of
+/// [`BitStore::mul_div`]. See [`max_precision`].
+///
+/// If this precision isn't valid, it's rejected by [`check_precision`].
+pub fn default_precision<M: Model>(max_denominator: M::B) -> u32 {
+    let frequency_bits = max_denominator.log2() + 1;
+    M::B::BITS.saturating_sub(frequency_bits)
+}
+
+/// The largest valid precision for a model.
+///
+/// If no precision is valid, this is rejected by [`check_precision`].
+pub fn max_precision<M: Model>(max_denominator: M::B) -> u32 {
+    let frequency_bits = max_denominator.log2() + 1;
+    (M::B::BITS - 1).min(M::B::WIDE_BITS.saturating_sub(frequency_bits))
+}
+
+pub fn check_precision<M: Model>(
+    max_denominator: M::B,
+    precision: u32,
+) -> Result<(), PrecisionError> {
     let frequency_bits = max_denominator.log2() + 1;
-    assert!(
-        (precision >= (frequency_bits + 2)),
-        "not enough bits of precision to prevent overflow/underflow",
-    );
-    assert!(
-        (frequency_bits + precision) <= M::B::BITS,
-        "not enough bits in BitStore to support the required precision",
-    );
+    if precision < frequency_bits + 2 {
+        return Err(PrecisionError::Precision {
+            needed: frequency_bits + 2,
+            available: precision,
+        });
+    }
+    if precision >= M::B::BITS {
+        return Err(PrecisionError::BitStore {
+            needed: precision + 1,
+            available: M::B::BITS,
+        });
+    }
+    if frequency_bits + precision > M::B::WIDE_BITS {
+        return Err(PrecisionError::BitStore {
+            needed: frequency_bits + precision,
+            available: M::B::WIDE_BITS,
+        });
+    }
+    Ok(())
+}
+
+pub fn assert_precision_sufficient<M: Model>(max_denominator: M::B, precision: u32) {
+    if let Err(e) = check_precision::<M>(max_denominator, precision) {
+        panic!("{e}");
+    }
 }
@@ -0,0 +1,149 @@
+//! Estimate the compressed size of a sequence of symbols, without producing
+//! any output.
+//!
+//! This is useful for choosing between models (or model parameters) by
+//! compressed size.
+//!
+//! ```
+//! # use std::convert::Infallible;
+//! # use std::ops::Range;
+//! #
+//! use arithmetic_coding::{Model, cost};
+//!
+//! # #[derive(Clone)]
+//! # struct MyModel;
+//! #
+//! # impl Model for MyModel {
+//! #     type B = u32;
+//! #     type Symbol = u8;
+//! #     type ValueError = Infallible;
+//! #
+//! #     fn probability(&self, symbol: Option<&u8>) -> Result<Range<u32>, Infallible> {
+//! #         Ok(symbol.map_or(0..1, |&s| u32::from(s) + 1..u32::from(s) + 2))
+//! #     }
+//! #
+//! #     fn symbol(&self, value: u32) -> Option<u8> {
+//! #         value.checked_sub(1).map(|s| u8::try_from(s).unwrap())
+//! #     }
+//! #
+//! #     fn max_denominator(&self) -> u32 {
+//! #         257
+//! #     }
+//! # }
+//! let cost = cost::estimate(MyModel, b"hello world".iter().copied()).unwrap();
+//!
+//! // each of the 11 symbols (and EOF) has a probability of 1/257
+//! assert!((cost.ideal_bits - 12.0 * 257_f64.log2()).abs() < 1e-9);
+//! assert!(cost.bits.abs_diff(cost.ideal_bits.ceil() as u64) <= 2);
+//! ```
+
+use std::{
+    iter::Sum,
+    ops::{Add, AddAssign},
+};
+
+use crate::{BitStore, Encoder, Error, Model, bits::BitCounter};
+
+/// The cost of encoding a sequence of symbols.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Cost {
+    /// The number of symbols, not including EOF
+    pub symbols: usize,
+
+    /// The exact number of bits the [`Encoder`] produces, including EOF and
+    /// the bits written when the encoder is flushed
+    pub bits: u64,
+
+    /// The ideal (information-theoretic) number of bits, including EOF. See
+    /// [`symbol_bits`].
+    pub ideal_bits: f64,
+}
+
+impl Add for Cost {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self {
+            symbols: self.symbols + rhs.symbols,
+            bits: self.bits + rhs.bits,
+            ideal_bits: self.ideal_bits + rhs.ideal_bits,
+        }
+    }
+}
+
+impl AddAssign for Cost {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sum for Cost {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::default(), Add::add)
+    }
+}
+
+/// Calculate the cost of encoding the symbols (followed by EOF) with the
+/// given model.
+///
+/// The symbols are run through an [`Encoder`] with the default precision,
+/// writing to a [`BitCounter`], so the bit count is exactly what
+/// [`Encoder::encode_all`] would produce.
+///
+/// # Errors
+///
+/// This function can fail if the [`Model`] rejects a symbol.
+pub fn estimate<M>(
+    model: M,
+    symbols: impl IntoIterator<Item = M::Symbol>,
+) -> Result<Cost, M::ValueError>
+where
+    M: Model,
+{
+    let mut counter = BitCounter::new();
+    let mut encoder = Encoder::new(model, &mut counter);
+    let mut cost = Cost::default();
+
+    for symbol in symbols {
+        cost.ideal_bits += symbol_bits(encoder.model(), Some(&symbol))?;
+        encoder.encode(Some(&symbol)).map_err(value_error)?;
+        cost.symbols += 1;
+    }
+    cost.ideal_bits += symbol_bits(encoder.model(), None)?;
+    encoder.encode(None).map_err(value_error)?;
+    infallible(encoder.flush());
+
+    cost.bits = counter.bits();
+    Ok(cost)
+}
+
+/// The ideal cost of encoding a symbol in the current state of the model, in
+/// bits.
+///
+/// This is the information content of the symbol, `-log2(p)`, where `p` is
+/// the probability given by [`Model::probability`] and
+/// [`Model::denominator`]. The number of bits actually produced by an encoder
+/// will differ slightly, due to the finite precision of the coder.
+///
+/// # Errors
+///
+/// This function can fail if the [`Model`] rejects the symbol.
+pub fn symbol_bits<M: Model>(model: &M, symbol: Option<&M::Symbol>) -> Result<f64, M::ValueError> {
+    let p = model.probability(symbol)?;
+    let width = (p.end - p.start).to_f64();
+    Ok((model.denominator().to_f64() / width).log2())
+}
+
+/// A [`BitCounter`] never fails
+fn value_error<E>(e: Error<E>) -> E {
+    match e {
+        Error::ValueError(e) => e,
+        Error::Io(_) => unreachable!("counting bits cannot fail"),
+        Error::InvalidProbability(_) => unreachable!("probabilities are only checked on request"),
+    }
+}
+
+/// A [`BitCounter`] never fails
+fn infallible<T>(result: std::io::Result<T>) -> T {
+    result.unwrap_or_else(|_| unreachable!("counting bits cannot fail"))
+}
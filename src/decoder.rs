@@ -1,12 +1,16 @@
 //! The [`Decoder`] half of the arithmetic coding library.
 
-use std::{io, ops::Range};
-
-use bitstream_io::BitRead;
+use alloc::vec::Vec;
+use core::ops::Range;
 
 use crate::{
-    BitStore, Model,
-    common::{self, assert_precision_sufficient},
+    BitStore, DecodeError, InvalidProbability, Model, PrecisionError, Termination,
+    bits::{BitSource, Rewind, SliceReader, io},
+    common::{
+        self, assert_precision_sufficient, check_denominator, check_precision, check_probability,
+        default_precision,
+    },
+    seekable::SyncPoint,
 };
 
 // this algorithm is derived from this article - https://marknelson.us/posts/2014/10/19/data-compression-with-arithmetic-coding.html
@@ -19,52 +23,42 @@ use crate::{
 pub struct Decoder<M, R>
 where
     M: Model,
-    R: BitRead,
+    R: BitSource,
 {
     model: M,
     state: State<M::B, R>,
-}
-
-trait BitReadExt {
-    fn next_bit(&mut self) -> io::Result<Option<bool>>;
-}
-
-impl<R: BitRead> BitReadExt for R {
-    fn next_bit(&mut self) -> io::Result<Option<bool>> {
-        match self.read_bit() {
-            Ok(bit) => Ok(Some(bit)),
-            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => Ok(None),
-            Err(e) => Err(e),
-        }
-    }
+    max_symbols: Option<usize>,
+    decoded: usize,
+    verified: usize,
 }
 
 impl<M, R> Decoder<M, R>
 where
     M: Model,
-    R: BitRead,
+    R: BitSource,
 {
     /// Construct a new [`Decoder`]
     ///
-    /// The 'precision' of the encoder is maximised, based on the number of bits
-    /// needed to represent the [`Model::denominator`]. 'precision' bits is
-    /// equal to [`u32::BITS`] - [`Model::denominator`] bits.
+    /// The 'precision' of the decoder is the number of bits of the [`BitStore`]
+    /// left over after those needed to represent the [`Model::denominator`].
+    /// Larger precisions are available (see
+    /// [`precision_range`](crate::precision_range)).
     ///
     /// # Panics
     ///
     /// The calculation of the number of bits used for 'precision' is subject to
     /// the following constraints:
     ///
-    /// - The total available bits is [`u32::BITS`]
+    /// - The precision must be less than [`BitStore::BITS`]
+    /// - The precision plus the bits needed to represent [`Model::denominator`]
+    ///   must be at most [`BitStore::WIDE_BITS`]
     /// - The precision must use at least 2 more bits than that needed to
     ///   represent [`Model::denominator`]
     ///
     /// If these constraints cannot be satisfied this method will panic in debug
     /// builds
     pub fn new(model: M, input: R) -> Self {
-        let frequency_bits = model.max_denominator().log2() + 1;
-        let precision = M::B::BITS - frequency_bits;
-
+        let precision = default_precision::<M>(model.max_denominator());
         Self::with_precision(model, input, precision)
     }
 
@@ -75,7 +69,9 @@ where
     /// The calculation of the number of bits used for 'precision' is subject to
     /// the following constraints:
     ///
-    /// - The total available bits is [`BitStore::BITS`]
+    /// - The precision must be less than [`BitStore::BITS`]
+    /// - The precision plus the bits needed to represent [`Model::denominator`]
+    ///   must be at most [`BitStore::WIDE_BITS`]
     /// - The precision must use at least 2 more bits than that needed to
     ///   represent [`Model::denominator`]
     ///
@@ -94,14 +90,157 @@ where
         #[cfg(debug_assertions)]
         assert_precision_sufficient::<M>(model.max_denominator(), state.state.precision);
 
-        Self { model, state }
+        Self {
+            model,
+            state,
+            max_symbols: None,
+            decoded: 0,
+            verified: 0,
+        }
+    }
+
+    /// Construct a new [`Decoder`], checking the precision in every build
+    /// profile.
+    ///
+    /// This is the fallible equivalent of [`Decoder::new`].
+    ///
+    /// # Errors
+    ///
+    /// This method fails if the [`BitStore`] is too small for the
+    /// [`Model::max_denominator`] (see
+    /// [`precision_range`](crate::precision_range)).
+    pub fn try_new(model: M, input: R) -> Result<Self, PrecisionError> {
+        let precision = default_precision::<M>(model.max_denominator());
+        Self::try_with_precision(model, input, precision)
+    }
+
+    /// Construct a new [`Decoder`] with a custom precision, checking the
+    /// precision in every build profile.
+    ///
+    /// This is the fallible equivalent of [`Decoder::with_precision`].
+    ///
+    /// # Errors
+    ///
+    /// This method fails if the precision is not valid for the [`Model`] (see
+    /// [`precision_range`](crate::precision_range)).
+    pub fn try_with_precision(model: M, input: R, precision: u32) -> Result<Self, PrecisionError> {
+        check_precision::<M>(model.max_denominator(), precision)?;
+        let state = State::new(precision, input);
+        Ok(Self::with_state(state, model))
+    }
+
+    /// Create a decoder from an existing [`State`] and [`Model`], checking the
+    /// precision in every build profile.
+    ///
+    /// This is the fallible equivalent of [`Decoder::with_state`].
+    ///
+    /// # Errors
+    ///
+    /// This method fails if the precision of the state is not valid for the
+    /// [`Model`].
+    pub fn try_with_state(state: State<M::B, R>, model: M) -> Result<Self, PrecisionError> {
+        check_precision::<M>(model.max_denominator(), state.state.precision)?;
+        Ok(Self::with_state(state, model))
+    }
+
+    /// Enable 'strict' decoding.
+    ///
+    /// By default, if the input runs out before EOF is reached, the decoder
+    /// behaves as though the input were padded with zeros. A truncated or
+    /// garbage stream may then decode to an arbitrary number of bogus symbols.
+    ///
+    /// In strict mode, the decoder tracks how many bits it has consumed beyond
+    /// the end of the input. A correctly terminated stream always contains
+    /// enough bits to decode its final symbol, so if a symbol would depend on
+    /// more missing bits than this, [`Decoder::decode`] returns
+    /// [`DecodeError::Truncated`].
+    ///
+    /// Note that truncation by only a few bits cannot always be detected.
+    #[must_use]
+    pub const fn strict(mut self) -> Self {
+        self.state.strict = true;
+        self
+    }
+
+    /// Enable 'checked' decoding.
+    ///
+    /// In checked mode, every probability and denominator returned by the
+    /// [`Model`] is validated before it is used, and [`Decoder::decode`]
+    /// returns [`DecodeError::InvalidProbability`] naming the offending
+    /// symbol. See [`Encoder::checked`](crate::Encoder::checked).
+    #[must_use]
+    pub const fn checked(mut self) -> Self {
+        self.state.checked = true;
+        self
+    }
+
+    /// Set how the stream was terminated by the [`Encoder`](crate::Encoder).
+    ///
+    /// This must match the termination used by the encoder in order to locate
+    /// the end of the stream with [`Decoder::finish`]. The default is
+    /// [`Termination::Standard`].
+    #[must_use]
+    pub const fn termination(mut self, termination: Termination) -> Self {
+        self.state.termination = termination;
+        self
+    }
+
+    /// Set the maximum number of symbols (not including EOF) that this
+    /// decoder will return.
+    ///
+    /// If the limit is reached and the next symbol is not EOF,
+    /// [`Decoder::decode`] returns [`DecodeError::TooManySymbols`]. This
+    /// guards against runaway decoding of corrupt streams.
+    #[must_use]
+    pub const fn max_symbols(mut self, limit: usize) -> Self {
+        self.max_symbols = Some(limit);
+        self
     }
 
     /// Return an iterator over the decoded symbols.
     ///
-    /// The iterator will continue returning symbols until EOF is reached
+    /// The iterator will continue returning symbols until EOF is reached, or
+    /// an error occurs.
     pub const fn decode_all(&'_ mut self) -> DecodeIter<'_, M, R> {
-        DecodeIter { decoder: self }
+        DecodeIter {
+            decoder: self,
+            failed: false,
+        }
+    }
+
+    /// Decode all remaining symbols into a [`Vec`].
+    ///
+    /// # Errors
+    ///
+    /// If decoding fails part way through, the symbols which were safely
+    /// decoded are returned along with the error (see [`PartialDecode`]). If
+    /// the input was truncated, only those symbols which were decoded without
+    /// depending on any bits beyond the end of the input are returned.
+    #[allow(clippy::type_complexity)]
+    pub fn decode_to_vec(
+        &mut self,
+    ) -> Result<Vec<M::Symbol>, PartialDecode<M::Symbol, M::ValueError>> {
+        let decoded_before = self.decoded;
+        let mut symbols = Vec::new();
+
+        loop {
+            match self.decode() {
+                Ok(Some(symbol)) => symbols.push(symbol),
+                Ok(None) => return Ok(symbols),
+                Err(error) => {
+                    if matches!(error, DecodeError::Truncated) {
+                        symbols.truncate(self.verified.saturating_sub(decoded_before));
+                    }
+                    return Err(PartialDecode { symbols, error });
+                }
+            }
+        }
+    }
+
+    /// The number of bits this decoder has consumed beyond the end of the
+    /// input.
+    pub const fn phantom_bits(&self) -> u64 {
+        self.state.phantom_bits
     }
 
     /// Read the next symbol from the stream of bits
@@ -110,27 +249,73 @@ where
     ///
     /// # Errors
     ///
-    /// This method can fail if the underlying [`BitRead`] cannot be read from.
-    #[allow(clippy::missing_panics_doc)]
-    pub fn decode(&mut self) -> io::Result<Option<M::Symbol>> {
+    /// This method can fail if
+    /// - the underlying [`BitSource`] cannot be read from
+    /// - the [`Model`] rejects the decoded symbol
+    /// - the decoded symbol is inconsistent with the decoded value, or the
+    ///   coding interval collapses. This indicates that the stream is corrupt,
+    ///   or that the [`Model`] is implemented incorrectly.
+    /// - the input is truncated (see [`Decoder::strict`])
+    /// - the symbol limit is exceeded (see [`Decoder::max_symbols`])
+    /// - the [`Model`] returns an invalid probability (see
+    ///   [`Decoder::checked`])
+    pub fn decode(&mut self) -> Result<Option<M::Symbol>, DecodeError<M::ValueError>> {
         self.state.initialise()?;
 
+        if self.state.strict && self.state.is_truncated() {
+            return Err(DecodeError::Truncated);
+        }
+
         let denominator = self.model.denominator();
-        debug_assert!(
-            denominator <= self.model.max_denominator(),
-            "denominator is greater than maximum!"
-        );
-        let value = self.state.value(denominator);
-        let symbol = self.model.symbol(value);
-
-        let p = self
+        let denominator_log2 = self.model.denominator_log2();
+        let index = self.decoded as u64;
+        let invalid = |kind| InvalidProbability { index, kind };
+
+        if self.state.checked {
+            check_denominator(denominator, denominator_log2, self.model.max_denominator())
+                .map_err(invalid)?;
+        } else {
+            debug_assert!(
+                denominator <= self.model.max_denominator(),
+                "denominator is greater than maximum!"
+            );
+        }
+        let value = self.state.value(denominator, denominator_log2);
+        let (symbol, p) = self
             .model
-            .probability(symbol.as_ref())
-            .expect("this should not be able to fail. Check the implementation of the model.");
+            .decode_symbol(value)
+            .map_err(DecodeError::ValueError)?;
+
+        if self.state.checked {
+            check_probability(
+                &p,
+                denominator,
+                denominator_log2,
+                self.model.max_denominator(),
+            )
+            .map_err(invalid)?;
+        }
+
+        if !p.contains(&value) || p.end > denominator {
+            return Err(DecodeError::CorruptStream);
+        }
+
+        if symbol.is_some() && self.max_symbols.is_some_and(|limit| self.decoded >= limit) {
+            return Err(DecodeError::TooManySymbols);
+        }
+
+        let verified = self.state.phantom_bits == 0;
 
-        self.state.scale(p, denominator)?;
+        self.state.scale(p, denominator, denominator_log2)?;
         self.model.update(symbol.as_ref());
 
+        if symbol.is_some() {
+            self.decoded += 1;
+            if verified {
+                self.verified += 1;
+            }
+        }
+
         Ok(symbol)
     }
 
@@ -145,52 +330,156 @@ where
         Decoder::with_state(self.state, model)
     }
 
+    /// Reuse the internal state of the Decoder with a new model, checking the
+    /// precision in every build profile.
+    ///
+    /// This is the fallible equivalent of [`Decoder::chain`].
+    ///
+    /// # Errors
+    ///
+    /// This method fails if the precision of the decoder is not valid for the
+    /// new [`Model`]. The decoder is consumed either way.
+    pub fn try_chain<X>(self, model: X) -> Result<Decoder<X, R>, PrecisionError>
+    where
+        X: Model<B = M::B>,
+    {
+        Decoder::try_with_state(self.state, model)
+    }
+
     /// Return the internal model and state of the decoder.
     pub fn into_inner(self) -> (M, State<M::B, R>) {
         (self.model, self.state)
     }
 }
 
-/// The iterator returned by the [`Model::decode_all`] method
+impl<M, R> Decoder<M, R>
+where
+    M: Model,
+    R: Rewind,
+{
+    /// Consume the decoder, returning the underlying reader positioned
+    /// immediately after the last bit of the stream, along with the number of
+    /// bits in the stream.
+    ///
+    /// The decoder reads ahead of the end of the stream, so the reader is
+    /// rewound (see [`Rewind`]) to the end of the stream. This allows other
+    /// data to follow the stream in the same input. This should be called
+    /// after decoding EOF, and the decoder must be configured with the same
+    /// [`Termination`] as the encoder.
+    ///
+    /// # Errors
+    ///
+    /// This method can fail if the reader cannot be rewound, or if the input
+    /// ends before the end of the stream.
+    pub fn finish(self) -> io::Result<(R, u64)> {
+        self.state.finish()
+    }
+}
+
+impl<'a, M> Decoder<M, SliceReader<'a>>
+where
+    M: Model,
+{
+    /// Construct a new [`Decoder`] which starts at a [`SyncPoint`] of a
+    /// [seekable](crate::seekable) stream.
+    ///
+    /// `input` is the whole stream, and `model` must be the initial model used
+    /// by the encoder. The decoder returns EOF at the end of the segment.
+    ///
+    /// The precision is chosen as for [`Decoder::new`].
+    ///
+    /// # Panics
+    ///
+    /// See [`Decoder::new`].
+    pub fn from_sync_point(model: M, input: &'a [u8], sync_point: &SyncPoint) -> Self {
+        let precision = default_precision::<M>(model.max_denominator());
+        Self::from_sync_point_with_precision(model, input, sync_point, precision)
+    }
+
+    /// Construct a new [`Decoder`] which starts at a [`SyncPoint`], with a
+    /// custom precision.
+    ///
+    /// # Panics
+    ///
+    /// See [`Decoder::with_precision`].
+    pub fn from_sync_point_with_precision(
+        model: M,
+        input: &'a [u8],
+        sync_point: &SyncPoint,
+        precision: u32,
+    ) -> Self {
+        let mut reader = SliceReader::new(input);
+        reader.seek(sync_point.bit_offset);
+
+        Self::with_precision(model, reader, precision)
+    }
+}
+
+/// The iterator returned by the [`Decoder::decode_all`] method
 #[allow(missing_debug_implementations)]
 pub struct DecodeIter<'a, M, R>
 where
     M: Model,
-    R: BitRead,
+    R: BitSource,
 {
     decoder: &'a mut Decoder<M, R>,
+    failed: bool,
 }
 
 impl<M, R> Iterator for DecodeIter<'_, M, R>
 where
     M: Model,
-    R: BitRead,
+    R: BitSource,
 {
-    type Item = io::Result<M::Symbol>;
+    type Item = Result<M::Symbol, DecodeError<M::ValueError>>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.decoder.decode().transpose()
+        if self.failed {
+            return None;
+        }
+        let item = self.decoder.decode().transpose();
+        self.failed = matches!(item, Some(Err(_)));
+        item
     }
 }
 
+/// An error which occurred part way through [`Decoder::decode_to_vec`], along
+/// with the symbols which were safely decoded before it.
+#[derive(Debug, thiserror::Error)]
+#[error("decoding failed after {} symbols", .symbols.len())]
+pub struct PartialDecode<S, E> {
+    /// The symbols which were safely decoded
+    pub symbols: Vec<S>,
+
+    /// The error which stopped decoding
+    #[source]
+    pub error: DecodeError<E>,
+}
+
 /// A convenience struct which stores the internal state of an [`Decoder`].
 #[derive(Debug)]
 pub struct State<B, R>
 where
     B: BitStore,
-    R: BitRead,
+    R: BitSource,
 {
     #[allow(clippy::struct_field_names)]
     state: common::State<B>,
     input: R,
     x: B,
     uninitialised: bool,
+    strict: bool,
+    checked: bool,
+    phantom_bits: u64,
+    shifts: u64,
+    pending: u32,
+    termination: Termination,
 }
 
 impl<B, R> State<B, R>
 where
     B: BitStore,
-    R: BitRead,
+    R: BitSource,
 {
     /// Create a new [`State`] from an input stream of bits with a given
     /// precision.
@@ -203,7 +492,45 @@ where
             input,
             x,
             uninitialised: true,
+            strict: false,
+            checked: false,
+            phantom_bits: 0,
+            shifts: 0,
+            pending: 0,
+            termination: Termination::default(),
+        }
+    }
+
+    pub(crate) const fn precision(&self) -> u32 {
+        self.state.precision
+    }
+
+    /// Read the next bit from the input, treating the end of the input as an
+    /// infinite stream of zeros.
+    fn next_bit(&mut self) -> io::Result<bool> {
+        let bit = self.input.next_bit()?;
+        if bit.is_none() {
+            self.phantom_bits += 1;
         }
+        Ok(bit == Some(true))
+    }
+
+    /// Whether the next symbol depends on more bits beyond the end of the
+    /// input than a correctly terminated stream would allow.
+    ///
+    /// When the encoder is flushed, it writes enough bits to identify the
+    /// final interval, regardless of what follows. With
+    /// [`Termination::Standard`] this leaves at most `precision - 2` bits of
+    /// the decoder's window past the end of the stream.
+    fn is_truncated(&self) -> bool {
+        self.phantom_bits > u64::from(self.state.precision - self.termination.min_bits())
+    }
+
+    /// The number of bits in the stream, assuming it terminates in the
+    /// current state.
+    fn stream_bits(&self) -> u64 {
+        let (_, tail) = self.state.tail(self.termination, self.pending);
+        self.shifts + u64::from(tail)
     }
 
     fn normalise(&mut self) -> io::Result<()> {
@@ -219,7 +546,9 @@ where
                 self.x = (self.x - self.state.half()) << 1;
             }
 
-            if self.input.next_bit()? == Some(true) {
+            self.shifts += 1;
+            self.pending = 0;
+            if self.next_bit()? {
                 self.x += B::ONE;
             }
         }
@@ -231,7 +560,9 @@ where
             self.state.high = ((self.state.high - self.state.quarter()) << 1) + B::ONE;
             self.x = (self.x - self.state.quarter()) << 1;
 
-            if self.input.next_bit()? == Some(true) {
+            self.shifts += 1;
+            self.pending += 1;
+            if self.next_bit()? {
                 self.x += B::ONE;
             }
         }
@@ -239,27 +570,52 @@ where
         Ok(())
     }
 
-    fn scale(&mut self, p: Range<B>, denominator: B) -> io::Result<()> {
-        self.state.scale(p, denominator);
-        self.normalise()
+    pub(crate) fn scale<E>(
+        &mut self,
+        p: Range<B>,
+        denominator: B,
+        denominator_log2: Option<u32>,
+    ) -> Result<(), DecodeError<E>> {
+        self.state
+            .scale(p, denominator, denominator_log2)
+            .map_err(|_| DecodeError::IntervalCollapsed)?;
+        Ok(self.normalise()?)
     }
 
-    fn value(&self, denominator: B) -> B {
+    /// The largest value whose scaled lower bound doesn't exceed the current
+    /// position in the interval.
+    ///
+    /// This is the inverse of [`common::State::scale`], and still needs a
+    /// division by the width of the interval for every symbol. The division
+    /// is done at the width of the [`BitStore`] if the precision leaves room
+    /// for the denominator (as the default precision does), and is only a
+    /// widening division for the larger precisions.
+    pub(crate) fn value(&self, denominator: B, denominator_log2: Option<u32>) -> B {
         let range = self.state.high - self.state.low + B::ONE;
-        ((self.x - self.state.low + B::ONE) * denominator - B::ONE) / range
+        let offset = self.x - self.state.low + B::ONE;
+        // the offset is at most 2^precision, so these products fit
+        match denominator_log2 {
+            Some(shift) if self.state.precision + shift < B::BITS => {
+                ((offset << shift) - B::ONE) / range
+            }
+            _ if self.state.precision + denominator.log2() < B::BITS => {
+                (offset * denominator - B::ONE) / range
+            }
+            _ => offset.mul_div_ceil(denominator, range) - B::ONE,
+        }
     }
 
     fn fill(&mut self) -> io::Result<()> {
         for _ in 0..self.state.precision {
             self.x <<= 1;
-            if self.input.next_bit()? == Some(true) {
+            if self.next_bit()? {
                 self.x += B::ONE;
             }
         }
         Ok(())
     }
 
-    fn initialise(&mut self) -> io::Result<()> {
+    pub(crate) fn initialise(&mut self) -> io::Result<()> {
         if self.uninitialised {
             self.fill()?;
             self.uninitialised = false;
@@ -267,3 +623,36 @@ where
         Ok(())
     }
 }
+
+impl<B, R> State<B, R>
+where
+    B: BitStore,
+    R: Rewind,
+{
+    /// Consume the state, returning the underlying reader positioned
+    /// immediately after the last bit of the stream, along with the number of
+    /// bits in the stream.
+    ///
+    /// See [`Decoder::finish`].
+    ///
+    /// # Errors
+    ///
+    /// This method can fail if the reader cannot be rewound, or if the input
+    /// ends before the end of the stream.
+    pub fn finish(mut self) -> io::Result<(R, u64)> {
+        self.initialise()?;
+
+        let stream_bits = self.stream_bits();
+        let read_bits = self.shifts + u64::from(self.state.precision) - self.phantom_bits;
+        let Some(overrun) = read_bits.checked_sub(stream_bits) else {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "input ended before the end of the stream",
+            ));
+        };
+
+        self.input.rewind(overrun)?;
+
+        Ok((self.input, stream_bits))
+    }
+}
@@ -1,12 +1,14 @@
 //! The [`Encoder`] half of the arithmetic coding library.
 
-use std::{io, ops::Range};
-
-use bitstream_io::BitWrite;
+use core::ops::Range;
 
 use crate::{
-    BitStore, Error, Model,
-    common::{self, assert_precision_sufficient},
+    BitStore, Error, InvalidProbability, InvalidProbabilityKind, Model, PrecisionError,
+    Termination,
+    bits::{BitSink, Truncate, io},
+    common::{
+        self, assert_precision_sufficient, check_precision, check_probability, default_precision,
+    },
 };
 
 // this algorithm is derived from this article - https://marknelson.us/posts/2014/10/19/data-compression-with-arithmetic-coding.html
@@ -19,38 +21,41 @@ use crate::{
 pub struct Encoder<M, W>
 where
     M: Model,
-    W: BitWrite,
+    W: BitSink,
 {
     model: M,
     state: State<M::B, W>,
+    symbols: u64,
 }
 
 impl<M, W> Encoder<M, W>
 where
     M: Model,
-    W: BitWrite,
+    W: BitSink,
 {
     /// Construct a new [`Encoder`].
     ///
-    /// The 'precision' of the encoder is maximised, based on the number of bits
-    /// needed to represent the [`Model::denominator`]. 'precision' bits is
-    /// equal to [`BitStore::BITS`] - [`Model::denominator`] bits. If you need
-    /// to set the precision manually, use [`Encoder::with_precision`].
+    /// The 'precision' of the encoder is the number of bits of the [`BitStore`]
+    /// left over after those needed to represent the [`Model::denominator`].
+    /// Larger precisions are available (see
+    /// [`precision_range`](crate::precision_range)). If you need to set the
+    /// precision manually, use [`Encoder::with_precision`].
     ///
     /// # Panics
     ///
     /// The calculation of the number of bits used for 'precision' is subject to
     /// the following constraints:
     ///
-    /// - The total available bits is [`BitStore::BITS`]
+    /// - The precision must be less than [`BitStore::BITS`]
+    /// - The precision plus the bits needed to represent [`Model::denominator`]
+    ///   must be at most [`BitStore::WIDE_BITS`]
     /// - The precision must use at least 2 more bits than that needed to
     ///   represent [`Model::denominator`]
     ///
     /// If these constraints cannot be satisfied this method will panic in debug
     /// builds
     pub fn new(model: M, bitwriter: W) -> Self {
-        let frequency_bits = model.max_denominator().log2() + 1;
-        let precision = M::B::BITS - frequency_bits;
+        let precision = default_precision::<M>(model.max_denominator());
         Self::with_precision(model, bitwriter, precision)
     }
 
@@ -61,7 +66,9 @@ where
     /// The calculation of the number of bits used for 'precision' is subject to
     /// the following constraints:
     ///
-    /// - The total available bits is [`BitStore::BITS`]
+    /// - The precision must be less than [`BitStore::BITS`]
+    /// - The precision plus the bits needed to represent [`Model::denominator`]
+    ///   must be at most [`BitStore::WIDE_BITS`]
     /// - The precision must use at least 2 more bits than that needed to
     ///   represent [`Model::denominator`]
     ///
@@ -79,7 +86,83 @@ where
     pub fn with_state(state: State<M::B, W>, model: M) -> Self {
         #[cfg(debug_assertions)]
         assert_precision_sufficient::<M>(model.max_denominator(), state.state.precision);
-        Self { model, state }
+        Self {
+            model,
+            state,
+            symbols: 0,
+        }
+    }
+
+    /// Construct a new [`Encoder`], checking the precision in every build
+    /// profile.
+    ///
+    /// This is the fallible equivalent of [`Encoder::new`].
+    ///
+    /// # Errors
+    ///
+    /// This method fails if the [`BitStore`] is too small for the
+    /// [`Model::max_denominator`] (see
+    /// [`precision_range`](crate::precision_range)).
+    pub fn try_new(model: M, bitwriter: W) -> Result<Self, PrecisionError> {
+        let precision = default_precision::<M>(model.max_denominator());
+        Self::try_with_precision(model, bitwriter, precision)
+    }
+
+    /// Construct a new [`Encoder`] with a custom precision, checking the
+    /// precision in every build profile.
+    ///
+    /// This is the fallible equivalent of [`Encoder::with_precision`].
+    ///
+    /// # Errors
+    ///
+    /// This method fails if the precision is not valid for the [`Model`] (see
+    /// [`precision_range`](crate::precision_range)).
+    pub fn try_with_precision(
+        model: M,
+        bitwriter: W,
+        precision: u32,
+    ) -> Result<Self, PrecisionError> {
+        check_precision::<M>(model.max_denominator(), precision)?;
+        let state = State::new(precision, bitwriter);
+        Ok(Self::with_state(state, model))
+    }
+
+    /// Create an encoder from an existing [`State`], checking the precision
+    /// in every build profile.
+    ///
+    /// This is the fallible equivalent of [`Encoder::with_state`].
+    ///
+    /// # Errors
+    ///
+    /// This method fails if the precision of the state is not valid for the
+    /// [`Model`].
+    pub fn try_with_state(state: State<M::B, W>, model: M) -> Result<Self, PrecisionError> {
+        check_precision::<M>(model.max_denominator(), state.state.precision)?;
+        Ok(Self::with_state(state, model))
+    }
+
+    /// Set how the stream is terminated when the encoder is flushed.
+    ///
+    /// The default is [`Termination::Standard`].
+    #[must_use]
+    pub const fn termination(mut self, termination: Termination) -> Self {
+        self.state.termination = termination;
+        self
+    }
+
+    /// Enable 'checked' encoding.
+    ///
+    /// By default, the probabilities returned by the [`Model`] are trusted.
+    /// An invalid probability (such as an empty range, or a range beyond
+    /// [`Model::denominator`]) silently corrupts the stream in release builds.
+    ///
+    /// In checked mode, every probability and denominator is validated before
+    /// it is used, and [`Encoder::encode`] returns
+    /// [`Error::InvalidProbability`] naming the offending symbol.
+    #[must_use]
+    pub const fn checked(mut self) -> Self {
+        self.state.checked = true;
+        self
     }
 
     /// Encode a stream of symbols into the provided output.
@@ -89,7 +172,7 @@ where
     ///
     /// # Errors
     ///
-    /// This method can fail if the underlying [`BitWrite`] cannot be written
+    /// This method can fail if the underlying [`BitSink`] cannot be written
     /// to.
     pub fn encode_all(
         mut self,
@@ -112,17 +195,31 @@ where
     ///
     /// # Errors
     ///
-    /// This method can fail if the underlying [`BitWrite`] cannot be written
-    /// to.
+    /// This method can fail if the underlying [`BitSink`] cannot be written
+    /// to, or if the [`Model`] rejects the symbol. In
+    /// [checked](Encoder::checked) mode, it also fails if the probability of
+    /// the symbol is invalid.
     pub fn encode(&mut self, symbol: Option<&M::Symbol>) -> Result<(), Error<M::ValueError>> {
         let p = self.model.probability(symbol).map_err(Error::ValueError)?;
         let denominator = self.model.denominator();
-        debug_assert!(
-            denominator <= self.model.max_denominator(),
-            "denominator is greater than maximum!"
-        );
+        let denominator_log2 = self.model.denominator_log2();
 
-        self.state.scale(p, denominator)?;
+        if self.state.checked {
+            self.state.scale_checked(
+                self.symbols,
+                p,
+                denominator,
+                denominator_log2,
+                self.model.max_denominator(),
+            )?;
+        } else {
+            debug_assert!(
+                denominator <= self.model.max_denominator(),
+                "denominator is greater than maximum!"
+            );
+            self.state.scale(p, denominator, denominator_log2)?;
+        }
+        self.symbols += 1;
         self.model.update(symbol);
 
         Ok(())
@@ -136,12 +233,22 @@ where
     ///
     /// # Errors
     ///
-    /// This method can fail if the underlying [`BitWrite`] cannot be written
+    /// This method can fail if the underlying [`BitSink`] cannot be written
     /// to.
     pub fn flush(self) -> io::Result<()> {
         self.state.flush()
     }
 
+    #[cfg(feature = "std")]
+    pub(crate) const fn model(&self) -> &M {
+        &self.model
+    }
+
+    #[cfg(feature = "std")]
+    pub(crate) const fn output_mut(&mut self) -> &mut W {
+        &mut self.state.output
+    }
+
     /// Return the internal model and state of the encoder.
     pub fn into_inner(self) -> (M, State<M::B, W>) {
         (self.model, self.state)
@@ -157,6 +264,86 @@ where
     {
         Encoder::with_state(self.state, model)
     }
+
+    /// Reuse the internal state of the Encoder with a new model, checking the
+    /// precision in every build profile.
+    ///
+    /// This is the fallible equivalent of [`Encoder::chain`].
+    ///
+    /// # Errors
+    ///
+    /// This method fails if the precision of the encoder is not valid for the
+    /// new [`Model`]. The encoder is consumed either way.
+    pub fn try_chain<X>(self, model: X) -> Result<Encoder<X, W>, PrecisionError>
+    where
+        X: Model<B = M::B>,
+    {
+        Encoder::try_with_state(self.state, model)
+    }
+}
+
+impl<M, W> Encoder<M, W>
+where
+    M: Model + Clone,
+    W: Truncate,
+{
+    /// Take a snapshot of the encoder, which can be restored with
+    /// [`Encoder::rollback`].
+    ///
+    /// This allows symbols to be encoded speculatively (for example, to
+    /// measure their cost with [`Encoder::position`]), and then undone.
+    pub fn checkpoint(&self) -> Checkpoint<M> {
+        Checkpoint {
+            model: self.model.clone(),
+            state: self.state.state.clone(),
+            pending: self.state.pending,
+            symbols: self.symbols,
+            position: self.state.output.position(),
+        }
+    }
+
+    /// Restore the encoder to a [`Checkpoint`].
+    ///
+    /// Any bits written since the checkpoint was taken are discarded from the
+    /// output, and the model is restored. The same checkpoint can be restored
+    /// any number of times, provided the encoder has not been rolled back to
+    /// an earlier point in the meantime.
+    ///
+    /// # Errors
+    ///
+    /// This method can fail if the output cannot be truncated to the position
+    /// of the checkpoint.
+    pub fn rollback(&mut self, checkpoint: &Checkpoint<M>) -> io::Result<()> {
+        self.state.output.truncate(checkpoint.position)?;
+        self.state.state = checkpoint.state.clone();
+        self.state.pending = checkpoint.pending;
+        self.symbols = checkpoint.symbols;
+        self.model = checkpoint.model.clone();
+        Ok(())
+    }
+
+    /// The number of bits written to the output so far.
+    ///
+    /// This doesn't include any pending bits which are held in the encoder
+    /// until the next bit is resolved.
+    pub fn position(&self) -> u64 {
+        self.state.output.position()
+    }
+}
+
+/// A snapshot of the state of an [`Encoder`].
+///
+/// See [`Encoder::checkpoint`].
+#[derive(Debug, Clone)]
+pub struct Checkpoint<M>
+where
+    M: Model,
+{
+    model: M,
+    state: common::State<M::B>,
+    pending: u32,
+    symbols: u64,
+    position: u64,
 }
 
 /// A convenience struct which stores the internal state of an [`Encoder`].
@@ -164,18 +351,20 @@ where
 pub struct State<B, W>
 where
     B: BitStore,
-    W: BitWrite,
+    W: BitSink,
 {
     #[allow(clippy::struct_field_names)]
     state: common::State<B>,
     pending: u32,
     output: W,
+    termination: Termination,
+    checked: bool,
 }
 
 impl<B, W> State<B, W>
 where
     B: BitStore,
-    W: BitWrite,
+    W: BitSink,
 {
     /// Manually construct a [`State`].
     ///
@@ -189,14 +378,47 @@ where
             state,
             pending,
             output,
+            termination: Termination::default(),
+            checked: false,
         }
     }
 
-    fn scale(&mut self, p: Range<B>, denominator: B) -> io::Result<()> {
-        self.state.scale(p, denominator);
+    pub(crate) const fn precision(&self) -> u32 {
+        self.state.precision
+    }
+
+    pub(crate) fn scale(
+        &mut self,
+        p: Range<B>,
+        denominator: B,
+        denominator_log2: Option<u32>,
+    ) -> io::Result<()> {
+        let scaled = self.state.scale(p, denominator, denominator_log2);
+        debug_assert!(
+            scaled.is_ok(),
+            "coding interval collapsed. Check the implementation of the model."
+        );
         self.normalise()
     }
 
+    /// Validate the probability before scaling.
+    fn scale_checked<E>(
+        &mut self,
+        index: u64,
+        p: Range<B>,
+        denominator: B,
+        denominator_log2: Option<u32>,
+        max_denominator: B,
+    ) -> Result<(), Error<E>> {
+        let invalid = |kind| InvalidProbability { index, kind };
+
+        check_probability(&p, denominator, denominator_log2, max_denominator).map_err(invalid)?;
+        self.state
+            .scale(p, denominator, denominator_log2)
+            .map_err(|_| invalid(InvalidProbabilityKind::TooSmall))?;
+        Ok(self.normalise()?)
+    }
+
     fn normalise(&mut self) -> io::Result<()> {
         while self.state.high < self.state.half() || self.state.low >= self.state.half() {
             if self.state.high < self.state.half() {
@@ -222,9 +444,9 @@ where
     }
 
     fn emit(&mut self, bit: bool) -> io::Result<()> {
-        self.output.write_bit(bit)?;
+        self.output.put_bit(bit)?;
         for _ in 0..self.pending {
-            self.output.write_bit(!bit)?;
+            self.output.put_bit(!bit)?;
         }
         self.pending = 0;
         Ok(())
@@ -234,17 +456,25 @@ where
     /// This method MUST be called when you finish writing symbols to ensure
     /// they are fully written to the output.
     ///
+    /// The number of bits written depends on the [`Termination`] of the
+    /// encoder.
+    ///
     /// # Errors
     ///
     /// This method can fail if the output cannot be written to
-    pub fn flush(mut self) -> io::Result<()> {
-        self.pending += 1;
-        if self.state.low <= self.state.quarter() {
-            self.emit(false)?;
-        } else {
-            self.emit(true)?;
+    pub fn flush(self) -> io::Result<()> {
+        self.finish()?;
+        Ok(())
+    }
+
+    /// Flush the internal buffer, and return the output.
+    pub(crate) fn finish(mut self) -> io::Result<W> {
+        let (bit, len) = self.state.tail(self.termination, self.pending);
+        if len > 0 {
+            self.pending += len - 1;
+            self.emit(bit)?;
         }
 
-        Ok(())
+        Ok(self.output)
     }
 }
@@ -0,0 +1,74 @@
+//! Interleaved arithmetic coding.
+//!
+//! The interleaved [`Encoder`] and [`Decoder`] distribute symbols round-robin
+//! across `N` independent coder states ('lanes'). Symbol `i` is coded by lane
+//! `i % N`. Because consecutive symbols are coded by different lanes, the
+//! arithmetic for each symbol doesn't depend on the result for the previous
+//! symbol, so the processor is free to overlap their execution.
+//!
+//! The symbols are still predicted by a single [`Model`](crate::Model), which
+//! is updated in order, so the interleaved coder accepts exactly the same
+//! models as the bitwise [`Encoder`](crate::Encoder). Models can be switched
+//! part way through a stream using [`Encoder::chain`] and [`Decoder::chain`].
+//!
+//! # Layout
+//!
+//! Each lane produces its own stream of bits, exactly as the bitwise
+//! [`Encoder`](crate::Encoder) would, padded with zeros to a whole number of
+//! bytes. The interleaved stream consists of
+//!
+//! 1. a header of `N - 1` little-endian `u32`s, holding the length in bytes of
+//!    each of the lanes except the last, followed by
+//! 2. the lanes, in order. The last lane occupies the remainder of the input.
+//!
+//! With `N = 1`, the output is identical to that of the bitwise encoder.
+//!
+//! # Example
+//!
+//! ```
+//! # use std::convert::Infallible;
+//! # use std::ops::Range;
+//! #
+//! use arithmetic_coding::{Model, interleaved};
+//!
+//! # #[derive(Clone)]
+//! # struct MyModel;
+//! #
+//! # impl Model for MyModel {
+//! #     type B = u32;
+//! #     type Symbol = u8;
+//! #     type ValueError = Infallible;
+//! #
+//! #     fn probability(&self, symbol: Option<&u8>) -> Result<Range<u32>, Infallible> {
+//! #         Ok(symbol.map_or(0..1, |&s| u32::from(s) + 1..u32::from(s) + 2))
+//! #     }
+//! #
+//! #     fn symbol(&self, value: u32) -> Option<u8> {
+//! #         value.checked_sub(1).map(|s| u8::try_from(s).unwrap())
+//! #     }
+//! #
+//! #     fn max_denominator(&self) -> u32 {
+//! #         257
+//! #     }
+//! # }
+//! let input = b"hello world".to_vec();
+//!
+//! let mut buffer = Vec::new();
+//! interleaved::Encoder::<_, 4>::new(MyModel, &mut buffer)
+//!     .encode_all(input.iter().copied())
+//!     .unwrap();
+//!
+//! let mut decoder = interleaved::Decoder::<_, 4>::new(MyModel, &buffer);
+//! let output: Vec<u8> = decoder.decode_all().map(Result::unwrap).collect();
+//!
+//! assert_eq!(input, output);
+//! ```
+
+pub mod decoder;
+pub mod encoder;
+
+pub use decoder::Decoder;
+pub use encoder::Encoder;
+
+/// The size in bytes of each lane length in the header
+const LENGTH_BYTES: usize = 4;
@@ -0,0 +1,177 @@
+//! The [`Decoder`] half of the interleaved coder.
+
+use super::LENGTH_BYTES;
+use crate::{
+    DecodeError, Model,
+    bits::SliceReader,
+    common::{assert_precision_sufficient, default_precision},
+    decoder::State,
+};
+
+/// An interleaved arithmetic decoder
+///
+/// Symbols are read round-robin from `N` independent lanes. See the
+/// [module-level documentation](super) for details of the input layout.
+#[derive(Debug)]
+pub struct Decoder<'a, M, const N: usize>
+where
+    M: Model,
+{
+    model: M,
+    lanes: [State<M::B, SliceReader<'a>>; N],
+    next: usize,
+}
+
+impl<'a, M, const N: usize> Decoder<'a, M, N>
+where
+    M: Model,
+{
+    /// Construct a new [`Decoder`].
+    ///
+    /// The precision must match that of the [`Encoder`](super::Encoder). This
+    /// method uses the same default precision as
+    /// [`Encoder::new`](super::Encoder::new).
+    ///
+    /// If the input is too short to contain the lanes described in its header,
+    /// the missing lanes are treated as empty.
+    ///
+    /// # Panics
+    ///
+    /// The precision is subject to the same constraints as the bitwise
+    /// [`Decoder`](crate::Decoder). If these constraints cannot be satisfied
+    /// this method will panic in debug builds
+    pub fn new(model: M, input: &'a [u8]) -> Self {
+        let precision = default_precision::<M>(model.max_denominator());
+        Self::with_precision(model, input, precision)
+    }
+
+    /// Construct a new [`Decoder`] with a custom precision.
+    ///
+    /// # Panics
+    ///
+    /// The precision is subject to the same constraints as the bitwise
+    /// [`Decoder`](crate::Decoder). If these constraints cannot be satisfied
+    /// this method will panic in debug builds
+    pub fn with_precision(model: M, input: &'a [u8], precision: u32) -> Self {
+        const { assert!(N > 0, "an interleaved decoder needs at least one lane") };
+
+        #[cfg(debug_assertions)]
+        assert_precision_sufficient::<M>(model.max_denominator(), precision);
+
+        let (header, mut rest) = input.split_at(input.len().min((N - 1) * LENGTH_BYTES));
+        let mut lengths = header.chunks_exact(LENGTH_BYTES).map(|length| {
+            let length = u32::from_le_bytes(length.try_into().unwrap());
+            usize::try_from(length).unwrap_or(usize::MAX)
+        });
+
+        let lanes = core::array::from_fn(|i| {
+            let length = if i == N - 1 {
+                rest.len()
+            } else {
+                lengths.next().unwrap_or(0).min(rest.len())
+            };
+            let (lane, remainder) = rest.split_at(length);
+            rest = remainder;
+            State::new(precision, SliceReader::new(lane))
+        });
+
+        Self {
+            model,
+            lanes,
+            next: 0,
+        }
+    }
+
+    /// Return an iterator over the decoded symbols.
+    ///
+    /// The iterator will continue returning symbols until EOF is reached, or
+    /// an error occurs.
+    pub const fn decode_all(&mut self) -> DecodeIter<'_, 'a, M, N> {
+        DecodeIter {
+            decoder: self,
+            failed: false,
+        }
+    }
+
+    /// Read the next symbol from the next lane
+    ///
+    /// This method will return `Ok(None)` when EOF is reached.
+    ///
+    /// # Errors
+    ///
+    /// This method can fail if
+    /// - the [`Model`] rejects the decoded symbol
+    /// - the decoded symbol is inconsistent with the decoded value, or the
+    ///   coding interval collapses. This indicates that the stream is corrupt,
+    ///   or that the [`Model`] is implemented incorrectly.
+    pub fn decode(&mut self) -> Result<Option<M::Symbol>, DecodeError<M::ValueError>> {
+        let lane = &mut self.lanes[self.next];
+        lane.initialise()?;
+
+        let denominator = self.model.denominator();
+        let denominator_log2 = self.model.denominator_log2();
+        debug_assert!(
+            denominator <= self.model.max_denominator(),
+            "denominator is greater than maximum!"
+        );
+        let value = lane.value(denominator, denominator_log2);
+        let (symbol, p) = self
+            .model
+            .decode_symbol(value)
+            .map_err(DecodeError::ValueError)?;
+
+        if !p.contains(&value) || p.end > denominator {
+            return Err(DecodeError::CorruptStream);
+        }
+
+        lane.scale(p, denominator, denominator_log2)?;
+        self.model.update(symbol.as_ref());
+        self.next = (self.next + 1) % N;
+
+        Ok(symbol)
+    }
+
+    /// Reuse the lanes of the Decoder with a new model.
+    ///
+    /// Allows for chaining multiple sequences of symbols from a single
+    /// interleaved stream.
+    pub fn chain<X>(self, model: X) -> Decoder<'a, X, N>
+    where
+        X: Model<B = M::B>,
+    {
+        #[cfg(debug_assertions)]
+        assert_precision_sufficient::<X>(model.max_denominator(), self.lanes[0].precision());
+
+        Decoder {
+            model,
+            lanes: self.lanes,
+            next: self.next,
+        }
+    }
+}
+
+/// The iterator returned by the [`Decoder::decode_all`] method
+#[allow(missing_debug_implementations)]
+pub struct DecodeIter<'d, 'a, M, const N: usize>
+where
+    M: Model,
+{
+    decoder: &'d mut Decoder<'a, M, N>,
+    failed: bool,
+}
+
+impl<M, const N: usize> Iterator for DecodeIter<'_, '_, M, N>
+where
+    M: Model,
+{
+    type Item = Result<M::Symbol, DecodeError<M::ValueError>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        let item = self.decoder.decode().transpose();
+        self.failed = matches!(item, Some(Err(_)));
+        item
+    }
+}
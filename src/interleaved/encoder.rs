@@ -0,0 +1,165 @@
+//! The [`Encoder`] half of the interleaved coder.
+
+use alloc::vec::Vec;
+
+use super::LENGTH_BYTES;
+use crate::{
+    Model,
+    bits::VecWriter,
+    common::{assert_precision_sufficient, default_precision},
+    encoder::State,
+};
+
+/// An interleaved arithmetic encoder
+///
+/// Symbols are distributed round-robin across `N` independent lanes. See the
+/// [module-level documentation](super) for details of the output layout.
+#[derive(Debug)]
+pub struct Encoder<'a, M, const N: usize>
+where
+    M: Model,
+{
+    model: M,
+    lanes: [State<M::B, VecWriter>; N],
+    next: usize,
+    output: &'a mut Vec<u8>,
+}
+
+impl<'a, M, const N: usize> Encoder<'a, M, N>
+where
+    M: Model,
+{
+    /// Construct a new [`Encoder`].
+    ///
+    /// Encoded bytes are appended to the given output buffer when the encoder
+    /// is flushed.
+    ///
+    /// The 'precision' of each lane is chosen as for
+    /// [`Encoder::new`](crate::Encoder::new). If you need to set the precision
+    /// manually, use [`Encoder::with_precision`].
+    ///
+    /// # Panics
+    ///
+    /// The precision is subject to the same constraints as the bitwise
+    /// [`Encoder`](crate::Encoder). If these constraints cannot be satisfied
+    /// this method will panic in debug builds
+    pub fn new(model: M, output: &'a mut Vec<u8>) -> Self {
+        let precision = default_precision::<M>(model.max_denominator());
+        Self::with_precision(model, output, precision)
+    }
+
+    /// Construct a new [`Encoder`] with a custom precision.
+    ///
+    /// The decoder must use the same precision.
+    ///
+    /// # Panics
+    ///
+    /// The precision is subject to the same constraints as the bitwise
+    /// [`Encoder`](crate::Encoder). If these constraints cannot be satisfied
+    /// this method will panic in debug builds
+    pub fn with_precision(model: M, output: &'a mut Vec<u8>, precision: u32) -> Self {
+        const { assert!(N > 0, "an interleaved encoder needs at least one lane") };
+
+        #[cfg(debug_assertions)]
+        assert_precision_sufficient::<M>(model.max_denominator(), precision);
+
+        Self {
+            model,
+            lanes: core::array::from_fn(|_| State::new(precision, VecWriter::new())),
+            next: 0,
+            output,
+        }
+    }
+
+    /// Encode a stream of symbols into the provided output.
+    ///
+    /// This method will encode all the symbols in the iterator, followed by EOF
+    /// (`None`), and then call [`Encoder::flush`].
+    ///
+    /// # Errors
+    ///
+    /// This method can fail if the [`Model`] rejects a symbol.
+    pub fn encode_all(
+        mut self,
+        symbols: impl IntoIterator<Item = M::Symbol>,
+    ) -> Result<(), M::ValueError> {
+        for symbol in symbols {
+            self.encode(Some(&symbol))?;
+        }
+        self.encode(None)?;
+        self.flush();
+        Ok(())
+    }
+
+    /// Encode a symbol into the next lane.
+    ///
+    /// When you finish encoding symbols, you must manually encode an EOF symbol
+    /// by calling [`Encoder::encode`] with `None`.
+    ///
+    /// The encoder must be manually flushed using [`Encoder::flush`].
+    ///
+    /// # Errors
+    ///
+    /// This method can fail if the [`Model`] rejects a symbol.
+    pub fn encode(&mut self, symbol: Option<&M::Symbol>) -> Result<(), M::ValueError> {
+        let p = self.model.probability(symbol)?;
+        let denominator = self.model.denominator();
+        debug_assert!(
+            denominator <= self.model.max_denominator(),
+            "denominator is greater than maximum!"
+        );
+
+        infallible(self.lanes[self.next].scale(p, denominator, self.model.denominator_log2()));
+        self.model.update(symbol);
+        self.next = (self.next + 1) % N;
+
+        Ok(())
+    }
+
+    /// Flush all the lanes, and write the interleaved stream to the output.
+    ///
+    /// This method must be called when you finish encoding symbols. This is
+    /// called automatically when you use [`Encoder::encode_all`].
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the output of a lane (other than the last)
+    /// exceeds [`u32::MAX`] bytes.
+    pub fn flush(self) {
+        let lanes = self.lanes.map(|lane| infallible(lane.finish()).finish());
+
+        for lane in &lanes[..N - 1] {
+            let length = u32::try_from(lane.len()).expect("lane is too long");
+            self.output
+                .extend_from_slice(&length.to_le_bytes()[..LENGTH_BYTES]);
+        }
+        for lane in &lanes {
+            self.output.extend_from_slice(lane);
+        }
+    }
+
+    /// Reuse the lanes of the Encoder with a new model.
+    ///
+    /// Allows for chaining multiple sequences of symbols into a single
+    /// interleaved stream. Symbols from the new model continue from the next
+    /// lane in the rotation.
+    pub fn chain<X>(self, model: X) -> Encoder<'a, X, N>
+    where
+        X: Model<B = M::B>,
+    {
+        #[cfg(debug_assertions)]
+        assert_precision_sufficient::<X>(model.max_denominator(), self.lanes[0].precision());
+
+        Encoder {
+            model,
+            lanes: self.lanes,
+            next: self.next,
+            output: self.output,
+        }
+    }
+}
+
+/// Lanes write to a [`VecWriter`], which never fails
+fn infallible<T>(result: crate::bits::io::Result<T>) -> T {
+    result.unwrap_or_else(|_| unreachable!("writing to a Vec cannot fail"))
+}
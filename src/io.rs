@@ -0,0 +1,303 @@
+//! [`std::io`] adapters for byte-oriented models.
+//!
+//! [`ArithmeticWriter`] compresses the bytes written to it, and
+//! [`ArithmeticReader`] decompresses the bytes read from it, so arithmetic
+//! coding can be added to an existing I/O pipeline by wrapping a file, socket,
+//! or buffer.
+//!
+//! ```
+//! use std::io::{Read, Write};
+//!
+//! # use std::convert::Infallible;
+//! # use std::ops::Range;
+//! use arithmetic_coding::{
+//!     Model,
+//!     io::{ArithmeticReader, ArithmeticWriter},
+//! };
+//!
+//! # #[derive(Clone)]
+//! # struct MyModel;
+//! #
+//! # impl Model for MyModel {
+//! #     type B = u32;
+//! #     type Symbol = u8;
+//! #     type ValueError = Infallible;
+//! #
+//! #     fn probability(&self, symbol: Option<&u8>) -> Result<Range<u32>, Infallible> {
+//! #         Ok(symbol.map_or(0..1, |&s| u32::from(s) + 1..u32::from(s) + 2))
+//! #     }
+//! #
+//! #     fn symbol(&self, value: u32) -> Option<u8> {
+//! #         value.checked_sub(1).map(|s| u8::try_from(s).unwrap())
+//! #     }
+//! #
+//! #     fn max_denominator(&self) -> u32 {
+//! #         257
+//! #     }
+//! # }
+//! let mut writer = ArithmeticWriter::new(MyModel, Vec::new());
+//! writer.write_all(b"hello world").unwrap();
+//! let buffer = writer.finish().unwrap();
+//!
+//! let mut reader = ArithmeticReader::new(MyModel, buffer.as_slice());
+//! let mut output = String::new();
+//! reader.read_to_string(&mut output).unwrap();
+//!
+//! assert_eq!(output, "hello world");
+//! ```
+
+use std::{
+    io::{self, BufRead, Read, Write},
+    string::ToString,
+    vec::Vec,
+};
+
+use bitstream_io::{BigEndian, BitReader, BitWrite, BitWriter};
+
+use crate::{DecodeError, Decoder, Encoder, Error, Model};
+
+/// The number of bytes decoded at a time by an [`ArithmeticReader`]
+const BUFFER_SIZE: usize = 4096;
+
+/// An [`io::Write`] adapter which encodes the bytes written to it.
+///
+/// The stream must be terminated by calling [`ArithmeticWriter::finish`]. If
+/// the writer is dropped without being finished, the stream is terminated
+/// automatically, but any errors are ignored.
+#[allow(missing_debug_implementations)]
+pub struct ArithmeticWriter<M, W>
+where
+    M: Model<Symbol = u8>,
+    W: Write,
+{
+    encoder: Option<Encoder<M, BitWriter<W, BigEndian>>>,
+}
+
+impl<M, W> ArithmeticWriter<M, W>
+where
+    M: Model<Symbol = u8>,
+    W: Write,
+{
+    /// Construct a new [`ArithmeticWriter`] which writes encoded bytes to
+    /// `writer`.
+    ///
+    /// The precision is chosen as for [`Encoder::new`].
+    pub fn new(model: M, writer: W) -> Self {
+        let encoder = Encoder::new(model, BitWriter::endian(writer, BigEndian));
+        Self {
+            encoder: Some(encoder),
+        }
+    }
+
+    /// Construct a new [`ArithmeticWriter`] with a custom precision.
+    pub fn with_precision(model: M, writer: W, precision: u32) -> Self {
+        let encoder =
+            Encoder::with_precision(model, BitWriter::endian(writer, BigEndian), precision);
+        Self {
+            encoder: Some(encoder),
+        }
+    }
+
+    /// Terminate the stream, and return the underlying writer.
+    ///
+    /// This encodes EOF, writes any remaining bits (padded to a whole byte),
+    /// and flushes the underlying writer.
+    ///
+    /// # Errors
+    ///
+    /// This method can fail if the underlying writer cannot be written to.
+    pub fn finish(mut self) -> io::Result<W> {
+        let Some(encoder) = self.encoder.take() else {
+            unreachable!("writer is only finished once");
+        };
+        finish(encoder)
+    }
+
+    const fn encoder(&mut self) -> &mut Encoder<M, BitWriter<W, BigEndian>> {
+        self.encoder
+            .as_mut()
+            .expect("writer is not used after being finished")
+    }
+}
+
+fn finish<M, W>(mut encoder: Encoder<M, BitWriter<W, BigEndian>>) -> io::Result<W>
+where
+    M: Model,
+    W: Write,
+{
+    encoder.encode(None).map_err(into_io_error)?;
+    let (_, state) = encoder.into_inner();
+    let mut bitwriter = state.finish()?;
+    bitwriter.byte_align()?;
+    bitwriter.flush()?;
+    Ok(bitwriter.into_writer())
+}
+
+impl<M, W> Write for ArithmeticWriter<M, W>
+where
+    M: Model<Symbol = u8>,
+    W: Write,
+{
+    /// Encode the bytes in `buf`.
+    ///
+    /// If a byte can't be encoded after some have been, the number of bytes
+    /// encoded so far is returned, and the error is left for the next call to
+    /// report.
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let encoder = self.encoder();
+        for (i, byte) in buf.iter().enumerate() {
+            if let Err(e) = encoder.encode(Some(byte)) {
+                return if i == 0 { Err(into_io_error(e)) } else { Ok(i) };
+            }
+        }
+        Ok(buf.len())
+    }
+
+    /// Flush the complete bytes which have been encoded so far to the
+    /// underlying writer.
+    ///
+    /// Up to a byte of output, and any bits which are still pending in the
+    /// encoder, are held back until the writer is finished.
+    fn flush(&mut self) -> io::Result<()> {
+        self.encoder().output_mut().flush()
+    }
+}
+
+impl<M, W> Drop for ArithmeticWriter<M, W>
+where
+    M: Model<Symbol = u8>,
+    W: Write,
+{
+    fn drop(&mut self) {
+        if let Some(encoder) = self.encoder.take() {
+            let _ = finish(encoder);
+        }
+    }
+}
+
+fn into_io_error<E: std::error::Error>(e: Error<E>) -> io::Error {
+    match e {
+        Error::Io(e) => e,
+        Error::ValueError(e) => io::Error::new(io::ErrorKind::InvalidInput, e.to_string()),
+        Error::InvalidProbability(e) => io::Error::new(io::ErrorKind::InvalidData, e),
+    }
+}
+
+/// An [`io::Read`] and [`io::BufRead`] adapter which decodes bytes on demand.
+///
+/// The decoder can't resume after an error, so once decoding fails, every
+/// subsequent read fails too.
+#[allow(missing_debug_implementations)]
+pub struct ArithmeticReader<M, R>
+where
+    M: Model<Symbol = u8>,
+    R: Read,
+{
+    decoder: Decoder<M, BitReader<R, BigEndian>>,
+    buffer: Vec<u8>,
+    position: usize,
+    eof: bool,
+    /// The kind of error which stopped decoding, if any
+    failed: Option<io::ErrorKind>,
+    /// The error which stopped decoding, until it's been reported, which is
+    /// once the bytes decoded before it have been read
+    error: Option<io::Error>,
+}
+
+impl<M, R> ArithmeticReader<M, R>
+where
+    M: Model<Symbol = u8>,
+    R: Read,
+{
+    /// Construct a new [`ArithmeticReader`] which decodes bytes from
+    /// `reader`.
+    ///
+    /// The precision is chosen as for [`Decoder::new`].
+    pub fn new(model: M, reader: R) -> Self {
+        Self::with_decoder(Decoder::new(model, BitReader::endian(reader, BigEndian)))
+    }
+
+    /// Construct a new [`ArithmeticReader`] with a custom precision.
+    pub fn with_precision(model: M, reader: R, precision: u32) -> Self {
+        Self::with_decoder(Decoder::with_precision(
+            model,
+            BitReader::endian(reader, BigEndian),
+            precision,
+        ))
+    }
+
+    fn with_decoder(decoder: Decoder<M, BitReader<R, BigEndian>>) -> Self {
+        Self {
+            decoder,
+            buffer: Vec::with_capacity(BUFFER_SIZE),
+            position: 0,
+            eof: false,
+            failed: None,
+            error: None,
+        }
+    }
+}
+
+impl<M, R> BufRead for ArithmeticReader<M, R>
+where
+    M: Model<Symbol = u8>,
+    R: Read,
+{
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if self.position == self.buffer.len() && !self.eof && self.failed.is_none() {
+            self.buffer.clear();
+            self.position = 0;
+            while self.buffer.len() < BUFFER_SIZE {
+                match self.decoder.decode() {
+                    Ok(Some(byte)) => self.buffer.push(byte),
+                    Ok(None) => {
+                        self.eof = true;
+                        break;
+                    }
+                    Err(e) => {
+                        let e = into_read_error(e);
+                        self.failed = Some(e.kind());
+                        self.error = Some(e);
+                        break;
+                    }
+                }
+            }
+        }
+
+        if self.position == self.buffer.len() {
+            if let Some(kind) = self.failed {
+                let e = self.error.take();
+                return Err(e.unwrap_or_else(|| {
+                    io::Error::new(kind, "the stream previously failed to decode")
+                }));
+            }
+        }
+
+        Ok(&self.buffer[self.position..])
+    }
+
+    fn consume(&mut self, amount: usize) {
+        self.position = (self.position + amount).min(self.buffer.len());
+    }
+}
+
+fn into_read_error<E: std::error::Error>(e: DecodeError<E>) -> io::Error {
+    match e {
+        DecodeError::Io(e) => e,
+        e => io::Error::new(io::ErrorKind::InvalidData, e.to_string()),
+    }
+}
+
+impl<M, R> Read for ArithmeticReader<M, R>
+where
+    M: Model<Symbol = u8>,
+    R: Read,
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let available = self.fill_buf()?;
+        let n = available.len().min(buf.len());
+        buf[..n].copy_from_slice(&available[..n]);
+        self.consume(n);
+        Ok(n)
+    }
+}
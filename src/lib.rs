@@ -1,16 +1,45 @@
 //! Arithmetic coding library
+//!
+//! # Features
+//!
+//! - `std` (enabled by default): support for [`std::io`] via [`bitstream_io`],
+//!   and the [`cost`], [`io`] and [`parallel`] modules. Without it, the crate
+//!   is `no_std`, and only requires `alloc`. Bits are written to and read from
+//!   the [`bits::BitSink`] and [`bits::BitSource`] traits, and errors are
+//!   reported with [`bits::io::Error`].
 
+#![no_std]
 #![deny(
     missing_docs,
     missing_debug_implementations,
     missing_copy_implementations
 )]
 
-pub use arithmetic_coding_core::{BitStore, Model, fixed_length, max_length, one_shot};
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
 
+use core::ops::RangeInclusive;
+
+pub use arithmetic_coding_core::{BitStore, Model, Reciprocal, fixed_length, max_length, one_shot};
+
+pub mod ans;
+pub mod binary;
+pub mod bits;
 mod common;
+#[cfg(feature = "std")]
+pub mod cost;
 pub mod decoder;
 pub mod encoder;
+pub mod interleaved;
+#[cfg(feature = "std")]
+pub mod io;
+#[cfg(feature = "std")]
+pub mod parallel;
+pub mod range_coder;
+pub mod seekable;
+#[cfg(feature = "testing")]
+pub mod verify;
 
 pub use decoder::Decoder;
 pub use encoder::Encoder;
@@ -19,8 +48,186 @@ pub use encoder::Encoder;
 #[derive(Debug, thiserror::Error)]
 pub enum Error<E> {
     /// Io error when reading/writing bits from a stream
-    Io(#[from] std::io::Error),
+    Io(#[from] bits::io::Error),
 
     /// Invalid symbol
     ValueError(E),
+
+    /// The [`Model`] returned an invalid probability (only checked in
+    /// [checked](Encoder::checked) mode)
+    InvalidProbability(#[from] InvalidProbability),
+}
+
+/// Errors that can occur during decoding
+#[derive(Debug, thiserror::Error)]
+pub enum DecodeError<E> {
+    /// Io error when reading bits from a stream
+    #[error(transparent)]
+    Io(#[from] bits::io::Error),
+
+    /// The [`Model`] rejected the decoded symbol
+    #[error("invalid symbol")]
+    ValueError(E),
+
+    /// The decoded value does not fall within the probability range of the
+    /// decoded symbol.
+    ///
+    /// This indicates that the stream is corrupt, or that the
+    /// [`Model::symbol`] and [`Model::probability`] methods are not
+    /// consistent with each other.
+    #[error("corrupt stream: decoded value is inconsistent with the model")]
+    CorruptStream,
+
+    /// The probability range of the decoded symbol is too small to be
+    /// represented at the current precision.
+    #[error("coding interval collapsed")]
+    IntervalCollapsed,
+
+    /// The input ended before the stream was complete.
+    #[error("truncated input")]
+    Truncated,
+
+    /// The maximum number of symbols was exceeded before EOF was reached.
+    #[error("maximum number of symbols exceeded")]
+    TooManySymbols,
+
+    /// The [`Model`] returned an invalid probability (only checked in
+    /// [checked](Decoder::checked) mode)
+    #[error(transparent)]
+    InvalidProbability(#[from] InvalidProbability),
+}
+
+/// A [`Model`] returned an invalid probability while coding a symbol.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("invalid probability for symbol {index}: {kind}")]
+pub struct InvalidProbability {
+    /// The position of the offending symbol in the stream, counting from zero.
+    /// EOF follows the last symbol. The count starts again from zero when the
+    /// coder is chained to a new [`Model`].
+    pub index: u64,
+
+    /// What was wrong with the probability
+    pub kind: InvalidProbabilityKind,
+}
+
+/// The ways in which a probability returned by a [`Model`] can be invalid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum InvalidProbabilityKind {
+    /// [`Model::denominator`] is zero
+    #[error("the denominator is zero")]
+    ZeroDenominator,
+
+    /// [`Model::denominator`] is greater than [`Model::max_denominator`]
+    #[error("the denominator is greater than the maximum denominator")]
+    DenominatorTooLarge,
+
+    /// [`Model::denominator_log2`] is inconsistent with
+    /// [`Model::denominator`]
+    #[error("the denominator is not two to the power of its logarithm")]
+    WrongDenominatorLog2,
+
+    /// The range returned by [`Model::probability`] is empty
+    #[error("the probability range is empty")]
+    EmptyRange,
+
+    /// The range returned by [`Model::probability`] extends beyond
+    /// [`Model::denominator`]
+    #[error("the probability range is greater than the denominator")]
+    OutOfBounds,
+
+    /// The range is too small to be represented at the precision of the
+    /// coder
+    #[error("the probability range is too small for the precision of the coder")]
+    TooSmall,
+}
+
+/// The precision of an [`Encoder`] or [`Decoder`] can't support the
+/// [`Model`].
+///
+/// See [`precision_range`] for the valid precisions of a model.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum PrecisionError {
+    /// The precision must use at least 2 more bits than that needed to
+    /// represent [`Model::max_denominator`].
+    #[error(
+        "not enough bits of precision to prevent overflow/underflow ({needed} bits needed, \
+         {available} available)"
+    )]
+    Precision {
+        /// The minimum precision
+        needed: u32,
+
+        /// The given precision
+        available: u32,
+    },
+
+    /// The precision must be less than [`BitStore::BITS`], and the precision
+    /// plus the bits needed to represent [`Model::max_denominator`] must fit
+    /// in [`BitStore::WIDE_BITS`].
+    #[error(
+        "not enough bits in BitStore to support the required precision ({needed} bits needed, \
+         {available} available)"
+    )]
+    BitStore {
+        /// The number of bits needed
+        needed: u32,
+
+        /// [`BitStore::BITS`] or [`BitStore::WIDE_BITS`]
+        available: u32,
+    },
+}
+
+/// The range of precisions which an [`Encoder`] or [`Decoder`] can use with
+/// the given model.
+///
+/// [`Encoder::new`] and [`Decoder::new`] use the number of bits of the
+/// [`BitStore`] not needed to represent [`Model::max_denominator`], so that
+/// their output doesn't depend on the version of this crate. Larger
+/// precisions, up to the end of this range, can be chosen with
+/// [`Encoder::with_precision`] and [`Decoder::with_precision`].
+///
+/// # Errors
+///
+/// This function returns an error if the [`BitStore`] is too small for any
+/// precision to be valid, in which case `needed` is the smallest valid
+/// precision, and `available` is the largest precision which fits in the
+/// [`BitStore`].
+pub fn precision_range<M: Model>(model: &M) -> Result<RangeInclusive<u32>, PrecisionError> {
+    let frequency_bits = model.max_denominator().log2() + 1;
+    let min = frequency_bits + 2;
+    let max = common::max_precision::<M>(model.max_denominator());
+
+    if min > max {
+        return Err(PrecisionError::Precision {
+            needed: min,
+            available: max,
+        });
+    }
+    Ok(min..=max)
+}
+
+/// How the [`Encoder`] terminates a stream of bits when it is flushed.
+///
+/// The [`Decoder`] must be configured with the same termination as the
+/// [`Encoder`] in order to locate the end of the stream (see
+/// [`Decoder::finish`]).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Termination {
+    /// Always terminate the stream with two bits (plus any pending bits).
+    #[default]
+    Standard,
+
+    /// Terminate the stream with the fewest bits needed to identify the final
+    /// interval (between zero and two, plus any pending bits).
+    Minimal,
+}
+
+impl Termination {
+    /// The fewest bits (excluding pending bits) this termination can emit.
+    pub(crate) const fn min_bits(self) -> u32 {
+        match self {
+            Self::Standard => 2,
+            Self::Minimal => 0,
+        }
+    }
 }
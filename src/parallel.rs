@@ -0,0 +1,233 @@
+//! Block-parallel arithmetic coding.
+//!
+//! The input is split into blocks of a fixed number of symbols. Each block is
+//! encoded independently, with a fresh [`Model`] created by a factory closure,
+//! so blocks can be encoded and decoded on separate threads.
+//!
+//! # Layout
+//!
+//! 1. the number of blocks, as a little-endian `u64`
+//! 2. the block index: the number of symbols in each block (not including EOF),
+//!    followed by its length in bytes, as little-endian `u64`s
+//! 3. the blocks, in order. Each block is the output of the bitwise
+//!    [`Encoder`], padded to a whole number of bytes.
+//!
+//! # Example
+//!
+//! ```
+//! # use std::convert::Infallible;
+//! # use std::ops::Range;
+//! #
+//! use arithmetic_coding::{Model, parallel};
+//!
+//! # #[derive(Clone)]
+//! # struct MyModel;
+//! #
+//! # impl Model for MyModel {
+//! #     type B = u32;
+//! #     type Symbol = u8;
+//! #     type ValueError = Infallible;
+//! #
+//! #     fn probability(&self, symbol: Option<&u8>) -> Result<Range<u32>, Infallible> {
+//! #         Ok(symbol.map_or(0..1, |&s| u32::from(s) + 1..u32::from(s) + 2))
+//! #     }
+//! #
+//! #     fn symbol(&self, value: u32) -> Option<u8> {
+//! #         value.checked_sub(1).map(|s| u8::try_from(s).unwrap())
+//! #     }
+//! #
+//! #     fn max_denominator(&self) -> u32 {
+//! #         257
+//! #     }
+//! # }
+//! let input = b"hello world".repeat(100);
+//!
+//! let buffer = parallel::encode(&input, 64, || MyModel).unwrap();
+//! let output = parallel::decode(&buffer, || MyModel).unwrap();
+//!
+//! assert_eq!(input, output);
+//! ```
+
+use std::{
+    num::NonZeroUsize,
+    panic,
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+    vec::Vec,
+};
+
+use crate::{
+    DecodeError, Decoder, Encoder, Error, Model,
+    bits::{SliceReader, VecWriter},
+};
+
+/// The size in bytes of each entry in the header
+const LENGTH_BYTES: usize = 8;
+
+/// Encode the symbols in parallel, in blocks of `block_size` symbols.
+///
+/// Each block is encoded with a new model returned by `model`.
+///
+/// # Errors
+///
+/// This function can fail if a [`Model`] rejects a symbol. If several blocks
+/// fail, the error from the first of them is returned.
+///
+/// # Panics
+///
+/// This function panics if `block_size` is zero.
+pub fn encode<M, F>(
+    symbols: &[M::Symbol],
+    block_size: usize,
+    model: F,
+) -> Result<Vec<u8>, Error<M::ValueError>>
+where
+    M: Model,
+    M::Symbol: Clone + Sync,
+    M::ValueError: Send,
+    F: Fn() -> M + Sync,
+{
+    assert!(block_size > 0, "block size must be greater than zero");
+
+    let blocks: Vec<&[M::Symbol]> = symbols.chunks(block_size).collect();
+    let encoded = for_each(blocks.len(), |i| {
+        let mut writer = VecWriter::new();
+        Encoder::new(model(), &mut writer).encode_all(blocks[i].iter().cloned())?;
+        Ok(writer.finish())
+    })
+    .into_iter()
+    .collect::<Result<Vec<_>, Error<M::ValueError>>>()?;
+
+    let header = (2 * encoded.len() + 1) * LENGTH_BYTES;
+    let mut output = Vec::with_capacity(header + encoded.iter().map(Vec::len).sum::<usize>());
+    output.extend_from_slice(&(encoded.len() as u64).to_le_bytes());
+    for (block, symbols) in encoded.iter().zip(&blocks) {
+        output.extend_from_slice(&(symbols.len() as u64).to_le_bytes());
+        output.extend_from_slice(&(block.len() as u64).to_le_bytes());
+    }
+    for block in &encoded {
+        output.extend_from_slice(block);
+    }
+
+    Ok(output)
+}
+
+/// Decode a stream produced by [`encode`] in parallel.
+///
+/// Each block is decoded with a new model returned by `model`.
+///
+/// # Errors
+///
+/// This function can fail if the block index is malformed, or if any of the
+/// blocks fails to decode (see [`Decoder::decode`]), including if a block
+/// holds more symbols than the block index records. If several blocks fail,
+/// the error from the first of them is returned.
+pub fn decode<M, F>(input: &[u8], model: F) -> Result<Vec<M::Symbol>, DecodeError<M::ValueError>>
+where
+    M: Model,
+    M::Symbol: Send,
+    M::ValueError: Send,
+    F: Fn() -> M + Sync,
+{
+    let blocks = blocks(input).ok_or(DecodeError::CorruptStream)?;
+
+    let decoded = for_each(blocks.len(), |i| {
+        Decoder::new(model(), SliceReader::new(blocks[i].bytes))
+            .max_symbols(blocks[i].symbols)
+            .decode_to_vec()
+            .map_err(|e| e.error)
+    });
+
+    let mut output = Vec::new();
+    for block in decoded {
+        output.extend(block?);
+    }
+    Ok(output)
+}
+
+/// A block of a stream produced by [`encode`]. See [`blocks`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Block<'a> {
+    /// The number of symbols in the block, not including EOF
+    pub symbols: usize,
+
+    /// The encoded block
+    pub bytes: &'a [u8],
+}
+
+/// Split a stream produced by [`encode`] into its blocks, using the block
+/// index.
+///
+/// This allows individual blocks to be decoded independently, with a fresh
+/// [`Decoder`] for each block, limited to the number of symbols in the block
+/// with [`Decoder::max_symbols`]. Returns `None` if the block index is
+/// malformed.
+#[must_use]
+pub fn blocks(input: &[u8]) -> Option<Vec<Block<'_>>> {
+    let (count, rest) = input.split_first_chunk::<LENGTH_BYTES>()?;
+    let count = usize::try_from(u64::from_le_bytes(*count)).ok()?;
+
+    let index_len = count.checked_mul(2 * LENGTH_BYTES)?;
+    if index_len > rest.len() {
+        return None;
+    }
+    let (index, mut rest) = rest.split_at(index_len);
+
+    index
+        .chunks_exact(2 * LENGTH_BYTES)
+        .map(|entry| {
+            let (symbols, length) = entry.split_at(LENGTH_BYTES);
+            let symbols = read_usize(symbols)?;
+            let length = read_usize(length)?;
+            if length > rest.len() {
+                return None;
+            }
+            let (bytes, remainder) = rest.split_at(length);
+            rest = remainder;
+            Some(Block { symbols, bytes })
+        })
+        .collect()
+}
+
+/// Read an entry of the block index
+fn read_usize(bytes: &[u8]) -> Option<usize> {
+    usize::try_from(u64::from_le_bytes(bytes.try_into().ok()?)).ok()
+}
+
+/// Evaluate `f` for each index in `0..count`, distributing the work across the
+/// available threads, and return the results in order.
+fn for_each<T, F>(count: usize, f: F) -> Vec<T>
+where
+    T: Send,
+    F: Fn(usize) -> T + Sync,
+{
+    let threads = thread::available_parallelism()
+        .map_or(1, NonZeroUsize::get)
+        .min(count);
+    let next = AtomicUsize::new(0);
+
+    let mut results: Vec<(usize, T)> = thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut results = Vec::new();
+                    loop {
+                        let i = next.fetch_add(1, Ordering::Relaxed);
+                        if i >= count {
+                            break results;
+                        }
+                        results.push((i, f(i)));
+                    }
+                })
+            })
+            .collect();
+
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().unwrap_or_else(|e| panic::resume_unwind(e)))
+            .collect()
+    });
+
+    results.sort_unstable_by_key(|(i, _)| *i);
+    results.into_iter().map(|(_, result)| result).collect()
+}
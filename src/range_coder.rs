@@ -0,0 +1,91 @@
+//! A byte-oriented range coder.
+//!
+//! This is an alternative backend to the bitwise [`Encoder`](crate::Encoder)
+//! and [`Decoder`](crate::Decoder). Rather than renormalising one bit at a
+//! time, the range coder renormalises a whole byte at a time (propagating
+//! carries into bytes that have already been produced), and reads from and
+//! writes to byte buffers directly.
+//!
+//! The range coder accepts exactly the same [`Model`] implementations as the
+//! bitwise coder, so switching backends doesn't require any changes to your
+//! models. Note that the two backends produce different (incompatible)
+//! output.
+//!
+//! # Example
+//!
+//! ```
+//! # use std::convert::Infallible;
+//! # use std::ops::Range;
+//! #
+//! use arithmetic_coding::{Model, range_coder};
+//!
+//! # #[derive(Clone)]
+//! # struct MyModel;
+//! #
+//! # impl Model for MyModel {
+//! #     type B = u32;
+//! #     type Symbol = u8;
+//! #     type ValueError = Infallible;
+//! #
+//! #     fn probability(&self, symbol: Option<&u8>) -> Result<Range<u32>, Infallible> {
+//! #         Ok(symbol.map_or(0..1, |&s| u32::from(s) + 1..u32::from(s) + 2))
+//! #     }
+//! #
+//! #     fn symbol(&self, value: u32) -> Option<u8> {
+//! #         value.checked_sub(1).map(|s| u8::try_from(s).unwrap())
+//! #     }
+//! #
+//! #     fn max_denominator(&self) -> u32 {
+//! #         257
+//! #     }
+//! # }
+//! let input = b"hello world".to_vec();
+//!
+//! let mut buffer = Vec::new();
+//! range_coder::Encoder::new(MyModel, &mut buffer)
+//!     .encode_all(input.iter().copied())
+//!     .unwrap();
+//!
+//! let mut decoder = range_coder::Decoder::new(MyModel, &buffer);
+//! let output: Vec<u8> = decoder.decode_all().map(Result::unwrap).collect();
+//!
+//! assert_eq!(input, output);
+//! ```
+
+use crate::{BitStore, Model};
+
+pub mod decoder;
+pub mod encoder;
+
+pub use decoder::Decoder;
+pub use encoder::Encoder;
+
+/// The number of bits of precision used by a range coder with the given
+/// [`BitStore`].
+///
+/// One bit is reserved for carry propagation, and the remainder is rounded
+/// down to a whole number of bytes.
+const fn precision<B: BitStore>() -> u32 {
+    ((B::BITS - 1) / 8) * 8
+}
+
+/// The width of a unit of probability, `range / denominator`.
+///
+/// This is a shift if the denominator is a power of two (see
+/// [`Model::denominator_log2`]).
+fn step<B: BitStore>(range: B, denominator: B, denominator_log2: Option<u32>) -> B {
+    denominator_log2.map_or_else(|| range / denominator, |shift| range >> shift)
+}
+
+/// The lower bound on the range after renormalisation.
+fn top<B: BitStore>() -> B {
+    B::ONE << (precision::<B>() - 8)
+}
+
+fn assert_precision_sufficient<M: Model>(max_denominator: M::B) {
+    let frequency_bits = max_denominator.log2() + 1;
+    assert!(
+        frequency_bits <= precision::<M::B>() - 8,
+        "not enough bits in BitStore to support the required precision",
+    );
+}
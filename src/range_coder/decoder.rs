@@ -0,0 +1,235 @@
+//! The [`Decoder`] half of the range coder.
+
+use core::ops::Range;
+
+use super::{assert_precision_sufficient, precision, step, top};
+use crate::{BitStore, DecodeError, Model};
+
+/// A byte-oriented range decoder
+///
+/// A range decoder converts a stream of bytes into a stream of some output
+/// symbol, using a predictive [`Model`].
+#[derive(Debug)]
+pub struct Decoder<'a, M>
+where
+    M: Model,
+{
+    model: M,
+    state: State<'a, M::B>,
+}
+
+impl<'a, M> Decoder<'a, M>
+where
+    M: Model,
+{
+    /// Construct a new [`Decoder`]
+    ///
+    /// # Panics
+    ///
+    /// The precision of the range coder is fixed by the size of the
+    /// [`BitStore`]. One bit is reserved for carry propagation, and a further
+    /// byte is reserved for renormalisation, so [`Model::max_denominator`]
+    /// must fit in the remaining bits.
+    ///
+    /// If this constraint cannot be satisfied this method will panic in debug
+    /// builds
+    pub fn new(model: M, input: &'a [u8]) -> Self {
+        let state = State::new(input);
+        Self::with_state(state, model)
+    }
+
+    /// Create a decoder from an existing [`State`] and [`Model`].
+    ///
+    /// This is useful for manually chaining a shared buffer through multiple
+    /// decoders.
+    pub fn with_state(state: State<'a, M::B>, model: M) -> Self {
+        #[cfg(debug_assertions)]
+        assert_precision_sufficient::<M>(model.max_denominator());
+
+        Self { model, state }
+    }
+
+    /// Return an iterator over the decoded symbols.
+    ///
+    /// The iterator will continue returning symbols until EOF is reached
+    pub const fn decode_all(&mut self) -> DecodeIter<'_, 'a, M> {
+        DecodeIter { decoder: self }
+    }
+
+    /// Read the next symbol from the stream of bytes
+    ///
+    /// This method will return `Ok(None)` when EOF is reached.
+    ///
+    /// If the input runs out before EOF is reached, it is treated as though it
+    /// were padded with zeros.
+    ///
+    /// # Errors
+    ///
+    /// This method can fail if
+    /// - the [`Model`] rejects the decoded symbol
+    /// - the decoded symbol is inconsistent with the decoded value. This
+    ///   indicates that the stream is corrupt, or that the [`Model`] is
+    ///   implemented incorrectly.
+    pub fn decode(&mut self) -> Result<Option<M::Symbol>, DecodeError<M::ValueError>> {
+        let denominator = self.model.denominator();
+        debug_assert!(
+            denominator <= self.model.max_denominator(),
+            "denominator is greater than maximum!"
+        );
+        let denominator_log2 = self.model.denominator_log2();
+        let value = self.state.value(denominator, denominator_log2);
+        let (symbol, p) = self
+            .model
+            .decode_symbol(value)
+            .map_err(DecodeError::ValueError)?;
+
+        if !p.contains(&value) || p.end > denominator {
+            return Err(DecodeError::CorruptStream);
+        }
+
+        self.state.scale(p, denominator, denominator_log2);
+        self.model.update(symbol.as_ref());
+
+        Ok(symbol)
+    }
+
+    /// Reuse the internal state of the Decoder with a new model.
+    ///
+    /// Allows for chaining multiple sequences of symbols from a single stream
+    /// of bytes
+    pub fn chain<X>(self, model: X) -> Decoder<'a, X>
+    where
+        X: Model<B = M::B>,
+    {
+        Decoder::with_state(self.state, model)
+    }
+
+    /// Return the internal model and state of the decoder.
+    pub fn into_inner(self) -> (M, State<'a, M::B>) {
+        (self.model, self.state)
+    }
+}
+
+/// The iterator returned by the [`Decoder::decode_all`] method
+#[allow(missing_debug_implementations)]
+pub struct DecodeIter<'d, 'a, M>
+where
+    M: Model,
+{
+    decoder: &'d mut Decoder<'a, M>,
+}
+
+impl<M> Iterator for DecodeIter<'_, '_, M>
+where
+    M: Model,
+{
+    type Item = Result<M::Symbol, DecodeError<M::ValueError>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.decoder.decode().transpose()
+    }
+}
+
+/// A convenience struct which stores the internal state of a [`Decoder`].
+#[derive(Debug)]
+pub struct State<'a, B>
+where
+    B: BitStore,
+{
+    /// The offset of the encoded value from the bottom of the current range
+    code: B,
+    range: B,
+    input: &'a [u8],
+}
+
+impl<'a, B> State<'a, B>
+where
+    B: BitStore,
+{
+    /// Create a new [`State`] from an input stream of bytes.
+    #[must_use]
+    pub fn new(input: &'a [u8]) -> Self {
+        let mut state = Self {
+            code: B::ZERO,
+            range: (B::ONE << precision::<B>()) - B::ONE,
+            input,
+        };
+
+        for _ in 0..precision::<B>() / 8 {
+            state.shift_in();
+        }
+
+        state
+    }
+
+    const fn next_byte(&mut self) -> u8 {
+        match self.input.split_first() {
+            Some((&byte, rest)) => {
+                self.input = rest;
+                byte
+            }
+            None => 0,
+        }
+    }
+
+    fn shift_in(&mut self) {
+        self.code = (self.code << 8) + B::from(self.next_byte());
+    }
+
+    /// The value of the next symbol.
+    ///
+    /// Finding the step is a shift if the denominator is a power of two, but
+    /// the code must still be divided by the step, so this costs one division
+    /// per symbol either way.
+    fn value(&self, denominator: B, denominator_log2: Option<u32>) -> B {
+        let r = step(self.range, denominator, denominator_log2);
+        let value = self.code / r;
+        if value < denominator {
+            value
+        } else {
+            denominator - B::ONE
+        }
+    }
+
+    fn scale(&mut self, p: Range<B>, denominator: B, denominator_log2: Option<u32>) {
+        let r = step(self.range, denominator, denominator_log2);
+        let offset = r * p.start;
+        self.code = self.code - offset;
+
+        // the last symbol takes up any remainder of the range
+        self.range = if p.end == denominator {
+            self.range - offset
+        } else {
+            r * (p.end - p.start)
+        };
+
+        self.normalise();
+    }
+
+    /// Decode a binary decision, where `bound` is the size of the lower
+    /// (`false`) part of the current range.
+    pub(crate) fn decode_bit(&mut self, bound: B) -> bool {
+        let bit = if self.code < bound {
+            self.range = bound;
+            false
+        } else {
+            self.code = self.code - bound;
+            self.range = self.range - bound;
+            true
+        };
+
+        self.normalise();
+        bit
+    }
+
+    pub(crate) const fn range(&self) -> B {
+        self.range
+    }
+
+    fn normalise(&mut self) {
+        while self.range < top() {
+            self.range <<= 8;
+            self.shift_in();
+        }
+    }
+}
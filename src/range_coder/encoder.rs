@@ -0,0 +1,232 @@
+//! The [`Encoder`] half of the range coder.
+
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use super::{assert_precision_sufficient, precision, step, top};
+use crate::{BitStore, Model};
+
+/// A byte-oriented range encoder
+///
+/// A range encoder converts a stream of symbols into a stream of bytes, using
+/// a predictive [`Model`].
+#[derive(Debug)]
+pub struct Encoder<'a, M>
+where
+    M: Model,
+{
+    model: M,
+    state: State<'a, M::B>,
+}
+
+impl<'a, M> Encoder<'a, M>
+where
+    M: Model,
+{
+    /// Construct a new [`Encoder`].
+    ///
+    /// Encoded bytes are appended to the given output buffer.
+    ///
+    /// # Panics
+    ///
+    /// The precision of the range coder is fixed by the size of the
+    /// [`BitStore`]. One bit is reserved for carry propagation, and a further
+    /// byte is reserved for renormalisation, so [`Model::max_denominator`]
+    /// must fit in the remaining bits.
+    ///
+    /// If this constraint cannot be satisfied this method will panic in debug
+    /// builds
+    pub fn new(model: M, output: &'a mut Vec<u8>) -> Self {
+        let state = State::new(output);
+        Self::with_state(state, model)
+    }
+
+    /// Create an encoder from an existing [`State`].
+    ///
+    /// This is useful for manually chaining a shared buffer through multiple
+    /// encoders.
+    pub fn with_state(state: State<'a, M::B>, model: M) -> Self {
+        #[cfg(debug_assertions)]
+        assert_precision_sufficient::<M>(model.max_denominator());
+        Self { model, state }
+    }
+
+    /// Encode a stream of symbols into the provided output.
+    ///
+    /// This method will encode all the symbols in the iterator, followed by EOF
+    /// (`None`), and then call [`Encoder::flush`].
+    ///
+    /// # Errors
+    ///
+    /// This method can fail if the [`Model`] rejects a symbol.
+    pub fn encode_all(
+        mut self,
+        symbols: impl IntoIterator<Item = M::Symbol>,
+    ) -> Result<(), M::ValueError> {
+        for symbol in symbols {
+            self.encode(Some(&symbol))?;
+        }
+        self.encode(None)?;
+        self.flush();
+        Ok(())
+    }
+
+    /// Encode a symbol into the provided output.
+    ///
+    /// When you finish encoding symbols, you must manually encode an EOF symbol
+    /// by calling [`Encoder::encode`] with `None`.
+    ///
+    /// The internal buffer must be manually flushed using [`Encoder::flush`].
+    ///
+    /// # Errors
+    ///
+    /// This method can fail if the [`Model`] rejects a symbol.
+    pub fn encode(&mut self, symbol: Option<&M::Symbol>) -> Result<(), M::ValueError> {
+        let p = self.model.probability(symbol)?;
+        let denominator = self.model.denominator();
+        debug_assert!(
+            denominator <= self.model.max_denominator(),
+            "denominator is greater than maximum!"
+        );
+
+        self.state
+            .scale(p, denominator, self.model.denominator_log2());
+        self.model.update(symbol);
+
+        Ok(())
+    }
+
+    /// Flush any pending bytes from the buffer
+    ///
+    /// This method must be called when you finish writing symbols to a stream
+    /// of bytes. This is called automatically when you use
+    /// [`Encoder::encode_all`].
+    pub fn flush(self) {
+        self.state.flush();
+    }
+
+    /// Return the internal model and state of the encoder.
+    pub fn into_inner(self) -> (M, State<'a, M::B>) {
+        (self.model, self.state)
+    }
+
+    /// Reuse the internal state of the Encoder with a new model.
+    ///
+    /// Allows for chaining multiple sequences of symbols into a single stream
+    /// of bytes
+    pub fn chain<X>(self, model: X) -> Encoder<'a, X>
+    where
+        X: Model<B = M::B>,
+    {
+        Encoder::with_state(self.state, model)
+    }
+}
+
+/// A convenience struct which stores the internal state of an [`Encoder`].
+#[derive(Debug)]
+pub struct State<'a, B>
+where
+    B: BitStore,
+{
+    low: B,
+    range: B,
+    /// The most recent byte which could still be modified by a carry.
+    ///
+    /// This is `None` until the first byte is shifted out of `low`.
+    cache: Option<u8>,
+    /// The number of `0xFF` bytes following the cache, which would also be
+    /// modified by a carry.
+    pending: usize,
+    output: &'a mut Vec<u8>,
+}
+
+impl<'a, B> State<'a, B>
+where
+    B: BitStore,
+{
+    /// Manually construct a [`State`].
+    ///
+    /// Normally this would be done automatically using the [`Encoder::new`]
+    /// method.
+    pub fn new(output: &'a mut Vec<u8>) -> Self {
+        Self {
+            low: B::ZERO,
+            range: (B::ONE << precision::<B>()) - B::ONE,
+            cache: None,
+            pending: 0,
+            output,
+        }
+    }
+
+    fn scale(&mut self, p: Range<B>, denominator: B, denominator_log2: Option<u32>) {
+        let r = step(self.range, denominator, denominator_log2);
+        let offset = r * p.start;
+        self.low += offset;
+
+        // the last symbol takes up any remainder of the range
+        self.range = if p.end == denominator {
+            self.range - offset
+        } else {
+            r * (p.end - p.start)
+        };
+
+        self.normalise();
+    }
+
+    /// Encode a binary decision, where `bound` is the size of the lower
+    /// (`false`) part of the current range.
+    pub(crate) fn encode_bit(&mut self, bound: B, bit: bool) {
+        if bit {
+            self.low += bound;
+            self.range = self.range - bound;
+        } else {
+            self.range = bound;
+        }
+
+        self.normalise();
+    }
+
+    pub(crate) const fn range(&self) -> B {
+        self.range
+    }
+
+    fn normalise(&mut self) {
+        while self.range < top() {
+            self.range <<= 8;
+            self.shift_low();
+        }
+    }
+
+    fn shift_low(&mut self) {
+        let carry = self.low >> precision::<B>();
+        let byte = (self.low >> (precision::<B>() - 8)).low_byte();
+
+        if carry > B::ZERO || byte != 0xFF {
+            let carry = carry.low_byte();
+            match self.cache {
+                Some(cache) => self.output.push(cache.wrapping_add(carry)),
+                // the first byte is the 'integer' part of the encoded value, which is always
+                // zero and so doesn't need to be written
+                None => debug_assert_eq!(carry, 0, "carry out of the first byte!"),
+            }
+            for _ in 0..self.pending {
+                self.output.push(0xFF_u8.wrapping_add(carry));
+            }
+            self.pending = 0;
+            self.cache = Some(byte);
+        } else {
+            self.pending += 1;
+        }
+
+        self.low = (self.low & (top::<B>() - B::ONE)) << 8;
+    }
+
+    /// Flush the internal buffer and write all remaining bytes to the output.
+    /// This method MUST be called when you finish writing symbols to ensure
+    /// they are fully written to the output.
+    pub fn flush(mut self) {
+        for _ in 0..=precision::<B>() / 8 {
+            self.shift_low();
+        }
+    }
+}
@@ -0,0 +1,561 @@
+//! Seekable arithmetic coding.
+//!
+//! A seekable stream is divided into segments. Each segment is a complete
+//! arithmetic-coded stream, terminated by EOF, and the segments are packed
+//! one after another with no padding in between. The start of each segment is
+//! a [`SyncPoint`], which records
+//!
+//! - the offset in bits of the segment from the start of the stream, and
+//! - the index of the first symbol in the segment.
+//!
+//! The sync points are collected in an [`Index`], which is returned
+//! separately from the encoded bytes, so that it can be stored wherever is
+//! convenient (see [`Index::to_bytes`]). Given the index, decoding can start
+//! at any sync point without decoding the segments before it.
+//!
+//! Each segment starts with a fresh copy of the initial model, so the index
+//! doesn't need to store any model state, and the decoder only needs the
+//! same initial model as the encoder.
+//!
+//! Every segment costs a few bits of termination overhead, and the model must
+//! learn the statistics of the data again, so segments shouldn't be too
+//! short.
+//!
+//! # Example
+//!
+//! ```
+//! # use std::convert::Infallible;
+//! # use std::ops::Range;
+//! #
+//! use arithmetic_coding::{Model, seekable};
+//!
+//! # #[derive(Debug, Clone)]
+//! # struct MyModel;
+//! #
+//! # impl Model for MyModel {
+//! #     type B = u32;
+//! #     type Symbol = u8;
+//! #     type ValueError = Infallible;
+//! #
+//! #     fn probability(&self, symbol: Option<&u8>) -> Result<Range<u32>, Infallible> {
+//! #         Ok(symbol.map_or(0..1, |&s| u32::from(s) + 1..u32::from(s) + 2))
+//! #     }
+//! #
+//! #     fn symbol(&self, value: u32) -> Option<u8> {
+//! #         value.checked_sub(1).map(|s| u8::try_from(s).unwrap())
+//! #     }
+//! #
+//! #     fn max_denominator(&self) -> u32 {
+//! #         257
+//! #     }
+//! # }
+//! let input = b"the quick brown fox jumps over the lazy dog";
+//!
+//! let (buffer, index) = seekable::Encoder::new(MyModel)
+//!     .interval(10)
+//!     .encode_all(input.iter().copied())
+//!     .unwrap();
+//! assert_eq!(index.sync_points.len(), 5);
+//!
+//! let mut decoder = seekable::Decoder::new(MyModel, &buffer, &index);
+//! decoder.seek(35).unwrap();
+//!
+//! let mut output = Vec::new();
+//! while let Some(symbol) = decoder.decode().unwrap() {
+//!     output.push(symbol);
+//! }
+//!
+//! assert_eq!(output, b"lazy dog");
+//! ```
+
+use alloc::{vec, vec::Vec};
+use core::mem;
+
+use crate::{
+    DecodeError, Model,
+    bits::{SliceReader, Truncate, VecWriter},
+    common::{assert_precision_sufficient, default_precision},
+    encoder::State,
+};
+
+/// A point in a seekable stream from which decoding can start.
+///
+/// The model is reset at every sync point, so decoding from a sync point
+/// starts with the initial model.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SyncPoint {
+    /// The offset in bits of the start of the segment
+    pub bit_offset: u64,
+
+    /// The index of the first symbol in the segment
+    pub symbol_index: u64,
+}
+
+/// The sync points of a seekable stream.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Index {
+    /// The sync points, in stream order
+    pub sync_points: Vec<SyncPoint>,
+
+    /// The total number of symbols in the stream, not including EOF
+    pub symbols: u64,
+}
+
+/// The encoded [`Index`] is truncated, or its sync points are out of order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("invalid seekable stream index")]
+pub struct InvalidIndex;
+
+impl Index {
+    /// The last sync point at or before the given symbol index.
+    ///
+    /// This is where decoding should start in order to reach the symbol.
+    #[must_use]
+    pub fn find(&self, symbol_index: u64) -> Option<&SyncPoint> {
+        self.segment(symbol_index)
+            .map(|segment| &self.sync_points[segment])
+    }
+
+    /// Encode the index as bytes, so that it can be stored alongside the
+    /// stream.
+    ///
+    /// The encoding is the total number of symbols, followed by the bit
+    /// offset and symbol index of each sync point, all as big-endian `u64`s.
+    #[must_use]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(8 + 16 * self.sync_points.len());
+        bytes.extend_from_slice(&self.symbols.to_be_bytes());
+        for sync_point in &self.sync_points {
+            bytes.extend_from_slice(&sync_point.bit_offset.to_be_bytes());
+            bytes.extend_from_slice(&sync_point.symbol_index.to_be_bytes());
+        }
+        bytes
+    }
+
+    /// Decode an index encoded by [`Index::to_bytes`].
+    ///
+    /// # Errors
+    ///
+    /// This method fails if `bytes` isn't a whole number of sync points, or
+    /// if the sync points aren't in stream order.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, InvalidIndex> {
+        let (symbols, rest) = bytes.split_first_chunk::<8>().ok_or(InvalidIndex)?;
+        let chunks = rest.chunks_exact(16);
+        if !chunks.remainder().is_empty() {
+            return Err(InvalidIndex);
+        }
+
+        let sync_points: Vec<SyncPoint> = chunks
+            .map(|chunk| SyncPoint {
+                bit_offset: read_u64(&chunk[..8]),
+                symbol_index: read_u64(&chunk[8..]),
+            })
+            .collect();
+        let symbols = u64::from_be_bytes(*symbols);
+
+        let ordered = sync_points.windows(2).all(|pair| {
+            pair[0].bit_offset <= pair[1].bit_offset && pair[0].symbol_index <= pair[1].symbol_index
+        });
+        let in_range = sync_points
+            .last()
+            .is_none_or(|sync_point| sync_point.symbol_index <= symbols);
+        if !ordered || !in_range {
+            return Err(InvalidIndex);
+        }
+
+        Ok(Self {
+            sync_points,
+            symbols,
+        })
+    }
+
+    fn segment(&self, symbol_index: u64) -> Option<usize> {
+        self.sync_points
+            .partition_point(|sync_point| sync_point.symbol_index <= symbol_index)
+            .checked_sub(1)
+    }
+
+    /// The number of symbols in a segment, not including EOF.
+    ///
+    /// A stream with no sync points is a single segment.
+    fn segment_symbols(&self, segment: usize) -> u64 {
+        let start = self
+            .sync_points
+            .get(segment)
+            .map_or(0, |sync_point| sync_point.symbol_index);
+        let end = self
+            .sync_points
+            .get(segment + 1)
+            .map_or(self.symbols, |sync_point| sync_point.symbol_index);
+        end.saturating_sub(start)
+    }
+}
+
+/// An encoder for seekable streams.
+///
+/// A new segment is started every [`Encoder::interval`] symbols, or whenever
+/// [`Encoder::sync`] is called.
+#[derive(Debug)]
+pub struct Encoder<M>
+where
+    M: Model + Clone,
+{
+    model: M,
+    initial: M,
+    state: State<M::B, VecWriter>,
+    interval: Option<u64>,
+    symbols: u64,
+    segment_symbols: u64,
+    sync_requested: bool,
+    sync_points: Vec<SyncPoint>,
+}
+
+impl<M> Encoder<M>
+where
+    M: Model + Clone,
+{
+    /// Construct a new [`Encoder`].
+    ///
+    /// The 'precision' of the encoder is chosen as for
+    /// [`Encoder::new`](crate::Encoder::new). If you need to set the precision
+    /// manually, use [`Encoder::with_precision`].
+    ///
+    /// # Panics
+    ///
+    /// The precision is subject to the same constraints as the bitwise
+    /// [`Encoder`](crate::Encoder). If these constraints cannot be satisfied
+    /// this method will panic in debug builds
+    pub fn new(model: M) -> Self {
+        let precision = default_precision::<M>(model.max_denominator());
+        Self::with_precision(model, precision)
+    }
+
+    /// Construct a new [`Encoder`] with a custom precision.
+    ///
+    /// The decoder must use the same precision.
+    ///
+    /// # Panics
+    ///
+    /// The precision is subject to the same constraints as the bitwise
+    /// [`Encoder`](crate::Encoder). If these constraints cannot be satisfied
+    /// this method will panic in debug builds
+    pub fn with_precision(model: M, precision: u32) -> Self {
+        #[cfg(debug_assertions)]
+        assert_precision_sufficient::<M>(model.max_denominator(), precision);
+
+        let start = SyncPoint {
+            bit_offset: 0,
+            symbol_index: 0,
+        };
+
+        Self {
+            initial: model.clone(),
+            model,
+            state: State::new(precision, VecWriter::new()),
+            interval: None,
+            symbols: 0,
+            segment_symbols: 0,
+            sync_requested: false,
+            sync_points: vec![start],
+        }
+    }
+
+    /// Start a new segment automatically every `symbols` symbols.
+    ///
+    /// By default, segments are only started by [`Encoder::sync`].
+    ///
+    /// # Panics
+    ///
+    /// This method panics if `symbols` is zero.
+    #[must_use]
+    pub fn interval(mut self, symbols: u64) -> Self {
+        assert!(symbols > 0, "sync interval must be greater than zero");
+        self.interval = Some(symbols);
+        self
+    }
+
+    /// Encode a stream of symbols, and return the encoded bytes and the
+    /// [`Index`] of the stream.
+    ///
+    /// # Errors
+    ///
+    /// This method can fail if the [`Model`] rejects a symbol.
+    pub fn encode_all(
+        mut self,
+        symbols: impl IntoIterator<Item = M::Symbol>,
+    ) -> Result<(Vec<u8>, Index), M::ValueError> {
+        for symbol in symbols {
+            self.encode(&symbol)?;
+        }
+        self.finish()
+    }
+
+    /// Encode a symbol.
+    ///
+    /// If a sync point is due, the current segment is terminated first, so
+    /// the new segment starts with this symbol.
+    ///
+    /// # Errors
+    ///
+    /// This method can fail if the [`Model`] rejects a symbol.
+    pub fn encode(&mut self, symbol: &M::Symbol) -> Result<(), M::ValueError> {
+        let interval_reached = self
+            .interval
+            .is_some_and(|interval| self.segment_symbols >= interval);
+        if self.sync_requested || interval_reached {
+            self.restart()?;
+        }
+
+        self.encode_symbol(Some(symbol))?;
+        self.symbols += 1;
+        self.segment_symbols += 1;
+
+        Ok(())
+    }
+
+    /// Place a sync point before the next symbol.
+    ///
+    /// This is useful for starting segments on record boundaries, so that each
+    /// record can be decoded independently.
+    pub const fn sync(&mut self) {
+        self.sync_requested = self.segment_symbols > 0;
+    }
+
+    /// Terminate the stream, and return the encoded bytes and the [`Index`]
+    /// of the stream.
+    ///
+    /// # Errors
+    ///
+    /// This method can fail if the [`Model`] rejects EOF.
+    pub fn finish(mut self) -> Result<(Vec<u8>, Index), M::ValueError> {
+        self.encode_symbol(None)?;
+        let output = infallible(self.state.finish()).finish();
+
+        let index = Index {
+            sync_points: self.sync_points,
+            symbols: self.symbols,
+        };
+        Ok((output, index))
+    }
+
+    fn encode_symbol(&mut self, symbol: Option<&M::Symbol>) -> Result<(), M::ValueError> {
+        let p = self.model.probability(symbol)?;
+        let denominator = self.model.denominator();
+        debug_assert!(
+            denominator <= self.model.max_denominator(),
+            "denominator is greater than maximum!"
+        );
+
+        infallible(
+            self.state
+                .scale(p, denominator, self.model.denominator_log2()),
+        );
+        self.model.update(symbol);
+
+        Ok(())
+    }
+
+    /// Terminate the current segment, and start a new one.
+    fn restart(&mut self) -> Result<(), M::ValueError> {
+        self.encode_symbol(None)?;
+
+        let precision = self.state.precision();
+        let state = mem::replace(&mut self.state, State::new(precision, VecWriter::new()));
+        let output = infallible(state.finish());
+        let bit_offset = output.position();
+        self.state = State::new(precision, output);
+        self.model = self.initial.clone();
+
+        self.sync_points.push(SyncPoint {
+            bit_offset,
+            symbol_index: self.symbols,
+        });
+        self.segment_symbols = 0;
+        self.sync_requested = false;
+
+        Ok(())
+    }
+}
+
+/// A decoder for seekable streams.
+///
+/// The decoder moves from one segment to the next automatically, so the
+/// whole stream can be decoded in order, but it can also [seek](Decoder::seek)
+/// to any symbol using the [`Index`] of the stream.
+#[derive(Debug)]
+pub struct Decoder<'a, M>
+where
+    M: Model + Clone,
+{
+    model: M,
+    input: &'a [u8],
+    index: &'a Index,
+    precision: u32,
+    segment: usize,
+    current: crate::Decoder<M, SliceReader<'a>>,
+    position: u64,
+    finished: bool,
+}
+
+impl<'a, M> Decoder<'a, M>
+where
+    M: Model + Clone,
+{
+    /// Construct a new [`Decoder`], positioned at the start of the stream.
+    ///
+    /// `model` must be the initial model used by the encoder.
+    ///
+    /// The 'precision' of the decoder is chosen as for
+    /// [`Decoder::new`](crate::Decoder::new). If you need to set the precision
+    /// manually, use [`Decoder::with_precision`].
+    ///
+    /// # Panics
+    ///
+    /// The precision is subject to the same constraints as the bitwise
+    /// [`Decoder`](crate::Decoder). If these constraints cannot be satisfied
+    /// this method will panic in debug builds
+    pub fn new(model: M, input: &'a [u8], index: &'a Index) -> Self {
+        let precision = default_precision::<M>(model.max_denominator());
+        Self::with_precision(model, input, index, precision)
+    }
+
+    /// Construct a new [`Decoder`] with a custom precision.
+    ///
+    /// The precision must match that of the encoder.
+    ///
+    /// # Panics
+    ///
+    /// The precision is subject to the same constraints as the bitwise
+    /// [`Decoder`](crate::Decoder). If these constraints cannot be satisfied
+    /// this method will panic in debug builds
+    pub fn with_precision(model: M, input: &'a [u8], index: &'a Index, precision: u32) -> Self {
+        let decoder = open(&model, input, index, 0, precision);
+
+        Self {
+            model,
+            input,
+            index,
+            precision,
+            segment: 0,
+            current: decoder,
+            position: 0,
+            finished: false,
+        }
+    }
+
+    /// The index of the next symbol to be decoded.
+    #[must_use]
+    pub const fn position(&self) -> u64 {
+        self.position
+    }
+
+    /// Move to the given symbol index.
+    ///
+    /// Decoding restarts from the nearest preceding sync point, and the
+    /// symbols between the sync point and the target are decoded and
+    /// discarded. Seeking beyond the end of the stream leaves the decoder at
+    /// the end.
+    ///
+    /// # Errors
+    ///
+    /// This method can fail for the same reasons as [`Decoder::decode`].
+    pub fn seek(&mut self, symbol_index: u64) -> Result<(), DecodeError<M::ValueError>> {
+        let segment = self.index.segment(symbol_index).unwrap_or(0);
+
+        self.current = open(&self.model, self.input, self.index, segment, self.precision);
+        self.segment = segment;
+        self.position = self
+            .index
+            .sync_points
+            .get(segment)
+            .map_or(0, |sync_point| sync_point.symbol_index);
+        self.finished = false;
+
+        while self.position < symbol_index {
+            if self.decode()?.is_none() {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Read the next symbol.
+    ///
+    /// This method will return `Ok(None)` at the end of the stream.
+    ///
+    /// # Errors
+    ///
+    /// This method can fail if
+    /// - the [`Model`] rejects the decoded symbol
+    /// - the decoded symbol is inconsistent with the decoded value, or the
+    ///   coding interval collapses (see
+    ///   [`Decoder::decode`](crate::Decoder::decode))
+    /// - a segment doesn't contain the number of symbols recorded in the
+    ///   [`Index`]. A segment which holds too many symbols fails with
+    ///   [`DecodeError::TooManySymbols`].
+    pub fn decode(&mut self) -> Result<Option<M::Symbol>, DecodeError<M::ValueError>> {
+        while !self.finished {
+            if let Some(symbol) = self.current.decode()? {
+                self.position += 1;
+                return Ok(Some(symbol));
+            }
+
+            match self.index.sync_points.get(self.segment + 1) {
+                Some(sync_point) if sync_point.symbol_index == self.position => {
+                    self.segment += 1;
+                    self.current = open(
+                        &self.model,
+                        self.input,
+                        self.index,
+                        self.segment,
+                        self.precision,
+                    );
+                }
+                Some(_) => return Err(DecodeError::CorruptStream),
+                None => self.finished = true,
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+/// Start decoding the given segment.
+///
+/// The decoder is limited to the number of symbols in the segment, so that a
+/// corrupt segment can't decode forever.
+fn open<'a, M>(
+    model: &M,
+    input: &'a [u8],
+    index: &Index,
+    segment: usize,
+    precision: u32,
+) -> crate::Decoder<M, SliceReader<'a>>
+where
+    M: Model + Clone,
+{
+    let decoder = index.sync_points.get(segment).map_or_else(
+        || crate::Decoder::with_precision(model.clone(), SliceReader::new(input), precision),
+        |sync_point| {
+            crate::Decoder::from_sync_point_with_precision(
+                model.clone(),
+                input,
+                sync_point,
+                precision,
+            )
+        },
+    );
+    let limit = usize::try_from(index.segment_symbols(segment)).unwrap_or(usize::MAX);
+    decoder.max_symbols(limit)
+}
+
+/// Read a big-endian `u64` from a slice of exactly 8 bytes
+fn read_u64(bytes: &[u8]) -> u64 {
+    u64::from_be_bytes(bytes.try_into().expect("slice is 8 bytes long"))
+}
+
+/// Segments are written to a [`VecWriter`], which never fails
+fn infallible<T>(result: crate::bits::io::Result<T>) -> T {
+    result.unwrap_or_else(|_| unreachable!("writing to a Vec cannot fail"))
+}
@@ -0,0 +1,780 @@
+//! Conformance checks for [`Model`] implementations.
+//!
+//! A model must satisfy a number of invariants, which are easy to get wrong,
+//! and which the coders don't check (unless they are in checked mode). A
+//! broken model usually produces a stream which silently decodes to the wrong
+//! symbols.
+//!
+//! The [`Verifier`] checks that, at every step of a sequence of symbols,
+//!
+//! - [`Model::denominator`] is non-zero, and no greater than
+//!   [`Model::max_denominator`],
+//! - the ranges returned by [`Model::probability`] are non-empty, and within
+//!   the denominator,
+//! - [`Model::symbol`] is the inverse of [`Model::probability`], so every value
+//!   below the denominator decodes to the symbol whose range contains it,
+//! - [`Model::decode_symbol`] agrees with [`Model::symbol`] and
+//!   [`Model::probability`],
+//! - the ranges are contiguous and don't overlap, and
+//! - EOF can be encoded (except where a [`fixed_length::Model`] expects more
+//!   symbols) and can't be decoded where it isn't expected.
+//!
+//! The model is [updated](Model::update) with each symbol in turn, and the
+//! checks are repeated after every update.
+//!
+//! This module requires the `testing` feature.
+//!
+//! ```
+//! # use std::convert::Infallible;
+//! # use std::ops::Range;
+//! #
+//! use arithmetic_coding::{Model, verify::Verifier};
+//!
+//! struct MyModel;
+//!
+//! impl Model for MyModel {
+//!     type B = u32;
+//!     type Symbol = u8;
+//!     type ValueError = Infallible;
+//!
+//!     fn probability(&self, symbol: Option<&u8>) -> Result<Range<u32>, Infallible> {
+//!         // oops, the ranges of 255 and EOF overlap
+//!         Ok(symbol.map_or(255..256, |&s| u32::from(s)..u32::from(s) + 1))
+//!     }
+//!
+//!     fn symbol(&self, value: u32) -> Option<u8> {
+//!         u8::try_from(value).ok()
+//!     }
+//!
+//!     fn max_denominator(&self) -> u32 {
+//!         256
+//!     }
+//! }
+//!
+//! let report = Verifier::new().model(MyModel, *b"hello");
+//! assert!(!report.is_ok());
+//! println!("{report}");
+//! ```
+
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{fmt, ops::Range};
+
+use crate::{BitStore, Model, fixed_length, max_length};
+
+/// How many values below the denominator are checked at each step.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Coverage {
+    /// Check every value.
+    ///
+    /// This takes time proportional to the denominator at every step.
+    #[default]
+    Exhaustive,
+
+    /// Check the first and last value of every range, and the given number of
+    /// pseudo-randomly sampled values.
+    ///
+    /// This takes time proportional to the number of symbols at every step.
+    Sample(usize),
+}
+
+/// Checks a model for violations of the [`Model`] invariants.
+///
+/// See the [module-level documentation](self) for the invariants.
+#[derive(Debug, Clone, Copy)]
+pub struct Verifier {
+    coverage: Coverage,
+    max_violations: usize,
+}
+
+impl Default for Verifier {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Verifier {
+    /// Construct a new [`Verifier`], with [`Coverage::Exhaustive`] which
+    /// stops after 100 violations.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            coverage: Coverage::Exhaustive,
+            max_violations: 100,
+        }
+    }
+
+    /// Set how many values are checked at each step.
+    #[must_use]
+    pub const fn coverage(mut self, coverage: Coverage) -> Self {
+        self.coverage = coverage;
+        self
+    }
+
+    /// Stop checking after the given number of violations.
+    #[must_use]
+    pub const fn max_violations(mut self, max_violations: usize) -> Self {
+        self.max_violations = max_violations;
+        self
+    }
+
+    /// Check a [`Model`] through a sequence of symbols.
+    ///
+    /// EOF must be encodable at every step.
+    pub fn model<M>(
+        &self,
+        model: M,
+        symbols: impl IntoIterator<Item = M::Symbol>,
+    ) -> Report<M::Symbol, M::B>
+    where
+        M: Model,
+        M::Symbol: Clone,
+    {
+        self.run(model, symbols, |_| true)
+    }
+
+    /// Check a [`fixed_length::Model`] through a sequence of symbols.
+    ///
+    /// The model is checked through a [`fixed_length::Wrapper`]. EOF must not
+    /// be decodable until [`fixed_length::Model::length`] symbols have been
+    /// coded. Any further symbols are ignored.
+    pub fn fixed_length<M>(
+        &self,
+        model: M,
+        symbols: impl IntoIterator<Item = M::Symbol>,
+    ) -> Report<M::Symbol, M::B>
+    where
+        M: fixed_length::Model,
+        M::Symbol: Clone,
+    {
+        let length = model.length();
+        self.run(
+            fixed_length::Wrapper::new(model),
+            symbols.into_iter().take(length),
+            |step| step == length,
+        )
+    }
+
+    /// Check a [`max_length::Model`] through a sequence of symbols.
+    ///
+    /// The model is checked through a [`max_length::Wrapper`]. Any symbols
+    /// after the first [`max_length::Model::max_length`] are ignored.
+    pub fn max_length<M>(
+        &self,
+        model: M,
+        symbols: impl IntoIterator<Item = M::Symbol>,
+    ) -> Report<M::Symbol, M::B>
+    where
+        M: max_length::Model,
+        M::Symbol: Clone,
+    {
+        let max_length = model.max_length();
+        self.run(
+            max_length::Wrapper::new(model),
+            symbols.into_iter().take(max_length),
+            |_| true,
+        )
+    }
+
+    fn run<M>(
+        &self,
+        mut model: M,
+        symbols: impl IntoIterator<Item = M::Symbol>,
+        eof_allowed: impl Fn(usize) -> bool,
+    ) -> Report<M::Symbol, M::B>
+    where
+        M: Model,
+        M::Symbol: Clone,
+    {
+        let mut checker = Checker {
+            step: 0,
+            eof_allowed: eof_allowed(0),
+            coverage: self.coverage,
+            max_violations: self.max_violations,
+            rng: Rng::default(),
+            violations: Vec::new(),
+        };
+        checker.state(&model);
+
+        for symbol in symbols {
+            if checker.is_full() {
+                break;
+            }
+
+            checker.symbol(&model, &symbol);
+            model.update(Some(&symbol));
+
+            checker.step += 1;
+            checker.eof_allowed = eof_allowed(checker.step);
+            checker.state(&model);
+        }
+
+        let truncated = checker.is_full();
+        let mut violations = checker.violations;
+        violations.truncate(self.max_violations);
+
+        Report {
+            steps: checker.step + 1,
+            violations,
+            truncated,
+        }
+    }
+}
+
+/// The state of a check in progress.
+struct Checker<S, B> {
+    step: usize,
+    eof_allowed: bool,
+    coverage: Coverage,
+    max_violations: usize,
+    rng: Rng,
+    violations: Vec<Violation<S, B>>,
+}
+
+impl<S, B> Checker<S, B>
+where
+    S: Clone,
+    B: BitStore,
+{
+    fn report(&mut self, kind: ViolationKind<S, B>) {
+        self.violations.push(Violation {
+            step: self.step,
+            kind,
+        });
+    }
+
+    fn is_full(&self) -> bool {
+        self.violations.len() >= self.max_violations
+    }
+
+    /// Check the state of the model.
+    fn state<M>(&mut self, model: &M)
+    where
+        M: Model<Symbol = S, B = B>,
+    {
+        let denominator = model.denominator();
+        let max_denominator = model.max_denominator();
+
+        if denominator == B::ZERO {
+            self.report(ViolationKind::ZeroDenominator);
+            return;
+        }
+        if denominator > max_denominator {
+            self.report(ViolationKind::DenominatorTooLarge {
+                denominator,
+                max_denominator,
+            });
+            return;
+        }
+        if let Some(log2) = model.denominator_log2() {
+            if log2 >= B::BITS || B::ONE << log2 != denominator {
+                self.report(ViolationKind::WrongDenominatorLog2 { denominator, log2 });
+                return;
+            }
+        }
+
+        if self.ranges(model, denominator) {
+            self.samples(model, denominator);
+        }
+
+        if self.eof_allowed {
+            self.eof(model, denominator);
+        }
+    }
+
+    /// Walk the ranges from zero to the denominator, checking that they are
+    /// contiguous and consistent with [`Model::symbol`].
+    ///
+    /// Returns `false` if the walk couldn't be completed.
+    fn ranges<M>(&mut self, model: &M, denominator: B) -> bool
+    where
+        M: Model<Symbol = S, B = B>,
+    {
+        let mut value = B::ZERO;
+        let mut previous = B::ZERO..B::ZERO;
+
+        while value < denominator && !self.is_full() {
+            let Some((symbol, range)) = self.decode(model, value, denominator) else {
+                return false;
+            };
+
+            if range.start != value {
+                self.report(ViolationKind::Overlap {
+                    symbol,
+                    range,
+                    other: previous,
+                });
+                return false;
+            }
+
+            let last = range.end - B::ONE;
+            match self.coverage {
+                Coverage::Exhaustive => {
+                    let mut value = range.start + B::ONE;
+                    while value <= last && !self.is_full() {
+                        self.interior(model, value, &range, denominator);
+                        value += B::ONE;
+                    }
+                }
+                Coverage::Sample(_) => {
+                    if last != range.start {
+                        self.interior(model, last, &range, denominator);
+                    }
+                }
+            }
+
+            value = range.end;
+            previous = range;
+        }
+
+        true
+    }
+
+    /// Check that a value within a range decodes to the symbol of that range.
+    fn interior<M>(&mut self, model: &M, value: B, range: &Range<B>, denominator: B)
+    where
+        M: Model<Symbol = S, B = B>,
+    {
+        if let Some((symbol, decoded)) = self.decode(model, value, denominator) {
+            if decoded != *range {
+                self.report(ViolationKind::Overlap {
+                    symbol,
+                    range: decoded,
+                    other: range.clone(),
+                });
+            }
+        }
+    }
+
+    fn samples<M>(&mut self, model: &M, denominator: B)
+    where
+        M: Model<Symbol = S, B = B>,
+    {
+        if let Coverage::Sample(samples) = self.coverage {
+            for _ in 0..samples {
+                if self.is_full() {
+                    break;
+                }
+                let value = self.rng.below(denominator);
+                self.decode(model, value, denominator);
+            }
+        }
+    }
+
+    /// Decode a value, and check that the range of the decoded symbol
+    /// contains it.
+    fn decode<M>(&mut self, model: &M, value: B, denominator: B) -> Option<(Option<S>, Range<B>)>
+    where
+        M: Model<Symbol = S, B = B>,
+    {
+        let symbol = model.symbol(value);
+
+        if symbol.is_none() && !self.eof_allowed {
+            self.report(ViolationKind::UnexpectedEof { value });
+            return None;
+        }
+
+        let range = self.probability(model, symbol.as_ref(), denominator)?;
+        if !range.contains(&value) {
+            self.report(ViolationKind::NotInverse {
+                value,
+                symbol,
+                range,
+            });
+            return None;
+        }
+
+        self.decode_symbol(model, value, &range);
+        Some((symbol, range))
+    }
+
+    /// Check that [`Model::decode_symbol`] agrees with [`Model::symbol`] and
+    /// [`Model::probability`].
+    fn decode_symbol<M>(&mut self, model: &M, value: B, expected: &Range<B>)
+    where
+        M: Model<Symbol = S, B = B>,
+    {
+        match model.decode_symbol(value) {
+            Ok((symbol, range)) => {
+                let consistent = range == *expected
+                    && model.probability(symbol.as_ref()).is_ok_and(|p| p == range);
+                if !consistent {
+                    self.report(ViolationKind::InconsistentDecodeSymbol {
+                        value,
+                        symbol,
+                        range,
+                    });
+                }
+            }
+            Err(error) => self.report(ViolationKind::Rejected {
+                symbol: model.symbol(value),
+                error: error.to_string(),
+            }),
+        }
+    }
+
+    /// Check that EOF can be encoded, and decoded again.
+    fn eof<M>(&mut self, model: &M, denominator: B)
+    where
+        M: Model<Symbol = S, B = B>,
+    {
+        if self.is_full() {
+            return;
+        }
+        if let Some(range) = self.probability(model, None, denominator) {
+            let symbol = model.symbol(range.start);
+            if symbol.is_some() {
+                self.report(ViolationKind::NotInverse {
+                    value: range.start,
+                    symbol,
+                    range,
+                });
+            }
+        }
+    }
+
+    /// Check that a symbol can be encoded, and decoded again.
+    fn symbol<M>(&mut self, model: &M, symbol: &S)
+    where
+        M: Model<Symbol = S, B = B>,
+    {
+        let denominator = model.denominator();
+        if denominator == B::ZERO || denominator > model.max_denominator() {
+            // already reported
+            return;
+        }
+
+        if let Some(range) = self.probability(model, Some(symbol), denominator) {
+            let decoded = model.symbol(range.start);
+            let round_trip = model
+                .probability(decoded.as_ref())
+                .is_ok_and(|decoded_range| decoded_range == range);
+            if !round_trip {
+                self.report(ViolationKind::NotInverse {
+                    value: range.start,
+                    symbol: decoded,
+                    range,
+                });
+            }
+        }
+    }
+
+    /// Get the probability of a symbol, and check that the range is valid.
+    fn probability<M>(&mut self, model: &M, symbol: Option<&S>, denominator: B) -> Option<Range<B>>
+    where
+        M: Model<Symbol = S, B = B>,
+    {
+        let range = match model.probability(symbol) {
+            Ok(range) => range,
+            Err(e) => {
+                self.report(ViolationKind::Rejected {
+                    symbol: symbol.cloned(),
+                    error: e.to_string(),
+                });
+                return None;
+            }
+        };
+
+        if range.start >= range.end {
+            self.report(ViolationKind::EmptyRange {
+                symbol: symbol.cloned(),
+                range,
+            });
+            return None;
+        }
+        if range.end > denominator {
+            self.report(ViolationKind::OutOfBounds {
+                symbol: symbol.cloned(),
+                range,
+                denominator,
+            });
+            return None;
+        }
+
+        Some(range)
+    }
+}
+
+/// The result of checking a model.
+#[derive(Debug, Clone)]
+pub struct Report<S, B> {
+    /// The number of states of the model which were checked. This is one more
+    /// than the number of symbols, unless checking stopped early.
+    pub steps: usize,
+
+    /// The violations which were found
+    pub violations: Vec<Violation<S, B>>,
+
+    /// Whether checking stopped early because the maximum number of
+    /// violations was reached (see [`Verifier::max_violations`])
+    pub truncated: bool,
+}
+
+impl<S, B> Report<S, B> {
+    /// Whether the model passed all the checks.
+    #[must_use]
+    pub fn is_ok(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+impl<S, B> fmt::Display for Report<S, B>
+where
+    S: fmt::Debug,
+    B: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_ok() {
+            return write!(f, "no violations in {} steps", self.steps);
+        }
+
+        write!(
+            f,
+            "{} violations in {} steps",
+            self.violations.len(),
+            self.steps
+        )?;
+        if self.truncated {
+            write!(f, " (stopped early)")?;
+        }
+        for violation in &self.violations {
+            write!(f, "\n  {violation}")?;
+        }
+        Ok(())
+    }
+}
+
+/// A violation of the [`Model`] invariants.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation<S, B> {
+    /// The number of symbols the model had been updated with when the
+    /// violation was found
+    pub step: usize,
+
+    /// What was wrong
+    pub kind: ViolationKind<S, B>,
+}
+
+impl<S, B> fmt::Display for Violation<S, B>
+where
+    S: fmt::Debug,
+    B: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "step {}: {}", self.step, self.kind)
+    }
+}
+
+/// The ways in which a model can violate the [`Model`] invariants.
+///
+/// A symbol of `None` is EOF.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ViolationKind<S, B> {
+    /// [`Model::denominator`] is zero
+    ZeroDenominator,
+
+    /// [`Model::denominator`] is greater than [`Model::max_denominator`]
+    DenominatorTooLarge {
+        /// The denominator
+        denominator: B,
+
+        /// The maximum denominator
+        max_denominator: B,
+    },
+
+    /// [`Model::denominator_log2`] is inconsistent with
+    /// [`Model::denominator`]
+    WrongDenominatorLog2 {
+        /// The denominator
+        denominator: B,
+
+        /// The base-2 logarithm returned by the model
+        log2: u32,
+    },
+
+    /// [`Model::probability`] returned an error for a symbol which the model
+    /// decoded, or which it was updated with
+    Rejected {
+        /// The symbol
+        symbol: Option<S>,
+
+        /// The error message
+        error: String,
+    },
+
+    /// [`Model::probability`] returned an empty range
+    EmptyRange {
+        /// The symbol
+        symbol: Option<S>,
+
+        /// The range
+        range: Range<B>,
+    },
+
+    /// [`Model::probability`] returned a range beyond the denominator
+    OutOfBounds {
+        /// The symbol
+        symbol: Option<S>,
+
+        /// The range
+        range: Range<B>,
+
+        /// The denominator
+        denominator: B,
+    },
+
+    /// [`Model::symbol`] returned a symbol whose range doesn't contain the
+    /// value
+    NotInverse {
+        /// The value
+        value: B,
+
+        /// The symbol returned by [`Model::symbol`]
+        symbol: Option<S>,
+
+        /// The range of the symbol
+        range: Range<B>,
+    },
+
+    /// [`Model::decode_symbol`] returned a symbol or range which differs from
+    /// [`Model::symbol`] and [`Model::probability`]
+    InconsistentDecodeSymbol {
+        /// The value
+        value: B,
+
+        /// The symbol returned by [`Model::decode_symbol`]
+        symbol: Option<S>,
+
+        /// The range returned by [`Model::decode_symbol`]
+        range: Range<B>,
+    },
+
+    /// The range of a symbol overlaps the range of another symbol
+    Overlap {
+        /// The symbol
+        symbol: Option<S>,
+
+        /// The range of the symbol
+        range: Range<B>,
+
+        /// The range it overlaps
+        other: Range<B>,
+    },
+
+    /// [`Model::symbol`] returned EOF where a [`fixed_length::Model`] expects
+    /// more symbols
+    UnexpectedEof {
+        /// The value
+        value: B,
+    },
+}
+
+impl<S, B> fmt::Display for ViolationKind<S, B>
+where
+    S: fmt::Debug,
+    B: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ZeroDenominator => write!(f, "the denominator is zero"),
+            Self::DenominatorTooLarge {
+                denominator,
+                max_denominator,
+            } => write!(
+                f,
+                "the denominator ({denominator:?}) is greater than the maximum denominator \
+                 ({max_denominator:?})"
+            ),
+            Self::WrongDenominatorLog2 { denominator, log2 } => {
+                write!(f, "the denominator ({denominator:?}) is not 2^{log2}")
+            }
+            Self::Rejected { symbol, error } => {
+                write!(f, "the probability of {symbol:?} is an error: {error}")
+            }
+            Self::EmptyRange { symbol, range } => {
+                write!(
+                    f,
+                    "the probability of {symbol:?} is an empty range ({range:?})"
+                )
+            }
+            Self::OutOfBounds {
+                symbol,
+                range,
+                denominator,
+            } => write!(
+                f,
+                "the probability of {symbol:?} ({range:?}) is beyond the denominator \
+                 ({denominator:?})"
+            ),
+            Self::NotInverse {
+                value,
+                symbol,
+                range,
+            } => write!(
+                f,
+                "the value {value:?} decodes to {symbol:?}, but its range ({range:?}) doesn't \
+                 contain the value"
+            ),
+            Self::InconsistentDecodeSymbol {
+                value,
+                symbol,
+                range,
+            } => write!(
+                f,
+                "the value {value:?} decodes to {symbol:?} ({range:?}) with decode_symbol, which \
+                 is inconsistent with symbol and probability"
+            ),
+            Self::Overlap {
+                symbol,
+                range,
+                other,
+            } => write!(
+                f,
+                "the range of {symbol:?} ({range:?}) overlaps another range ({other:?})"
+            ),
+            Self::UnexpectedEof { value } => {
+                write!(
+                    f,
+                    "the value {value:?} decodes to EOF before the end of the input"
+                )
+            }
+        }
+    }
+}
+
+/// A small deterministic pseudo-random number generator (xorshift64) for
+/// sampling values.
+#[derive(Debug, Clone, Copy)]
+struct Rng(u64);
+
+impl Default for Rng {
+    fn default() -> Self {
+        Self(0x9E37_79B9_7F4A_7C15)
+    }
+}
+
+impl Rng {
+    const fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// A value below the given bound.
+    fn below<B: BitStore>(&mut self, bound: B) -> B {
+        let mut value = B::ZERO;
+        let mut bits = 0;
+        while bits < B::BITS {
+            for byte in self.next().to_le_bytes() {
+                if bits < B::BITS {
+                    value = (value << 8) + B::from(byte);
+                    bits += 8;
+                }
+            }
+        }
+        value - (value / bound) * bound
+    }
+}
@@ -0,0 +1,53 @@
+use fenwick_model::{Adaptation, context_switching, simple};
+use test_case::test_case;
+
+mod common;
+
+// a maximum denominator this small forces the models to rescale many times
+#[test_case(Adaptation::new(); "default")]
+#[test_case(Adaptation::new().increment(32); "large increment")]
+#[test_case(Adaptation::new().rescale_threshold(1 << 10); "low threshold")]
+#[test_case(Adaptation::new().decay(64, 4); "decay")]
+fn fenwick_simple(adaptation: Adaptation) {
+    let model = simple::FenwickModel::builder(256, 1 << 12)
+        .adaptation(adaptation)
+        .build();
+    common::round_trip(model, &common::sherlock_symbols()[..100_000]);
+}
+
+#[test_case(Adaptation::new(); "default")]
+#[test_case(Adaptation::new().increment(32); "large increment")]
+#[test_case(Adaptation::new().decay(64, 4); "decay")]
+fn fenwick_context_switching(adaptation: Adaptation) {
+    let model = context_switching::FenwickModel::builder(256, 1 << 10)
+        .adaptation(adaptation)
+        .build();
+    common::round_trip(model, &common::sherlock_symbols()[..100_000]);
+}
+
+/// A threshold too small to leave room for the alphabet after rescaling
+/// shouldn't stop the model working.
+#[test]
+fn threshold_below_alphabet() {
+    let model = simple::FenwickModel::<usize>::builder(256, 1 << 12)
+        .adaptation(Adaptation::new().rescale_threshold(100))
+        .build();
+    common::round_trip(model, &common::sherlock_symbols()[..10_000]);
+}
+
+/// Rescaling lets the model track a distribution which changes partway
+/// through the input.
+#[test]
+fn tracks_changing_distribution() {
+    let input: Vec<usize> = (0..100_000).map(|i| i / 50_000 + (i % 3)).collect();
+
+    let model = |adaptation| {
+        simple::FenwickModel::<usize>::builder(4, 1 << 20)
+            .adaptation(adaptation)
+            .build()
+    };
+    let slow = common::round_trip(model(Adaptation::new()), &input);
+    let fast = common::round_trip(model(Adaptation::new().rescale_threshold(1 << 10)), &input);
+
+    assert!(fast < slow, "{fast} >= {slow}");
+}
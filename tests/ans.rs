@@ -0,0 +1,176 @@
+use std::{convert::Infallible, ops::Range};
+
+use arithmetic_coding::{DecodeError, Model, ans, fixed_length, max_length};
+use fenwick_model::{context_switching, simple};
+use test_case::test_case;
+
+mod common;
+
+fn round_trip<M>(model: M, input: &[M::Symbol])
+where
+    M: Model + Clone,
+    M::Symbol: PartialEq + std::fmt::Debug + Clone,
+{
+    let mut buffer = Vec::new();
+    ans::Encoder::new(model.clone(), &mut buffer)
+        .encode_all(input.to_owned())
+        .unwrap();
+
+    let mut decoder = ans::Decoder::new(model, &buffer);
+    let output: Vec<_> = decoder.decode_all().map(Result::unwrap).collect();
+
+    assert_eq!(input, output.as_slice());
+}
+
+#[derive(Debug, PartialEq, Clone, Eq)]
+pub enum Symbol {
+    A,
+    B,
+    C,
+}
+
+#[derive(Clone)]
+pub struct FixedLengthModel;
+
+impl fixed_length::Model for FixedLengthModel {
+    type B = u32;
+    type Symbol = Symbol;
+    type ValueError = Infallible;
+
+    fn probability(&self, symbol: &Self::Symbol) -> Result<Range<u32>, Self::ValueError> {
+        match symbol {
+            Symbol::A => Ok(0..1),
+            Symbol::B => Ok(1..2),
+            Symbol::C => Ok(2..3),
+        }
+    }
+
+    fn symbol(&self, value: u32) -> Self::Symbol {
+        match value {
+            0..1 => Symbol::A,
+            1..2 => Symbol::B,
+            2..3 => Symbol::C,
+            _ => unreachable!(),
+        }
+    }
+
+    fn max_denominator(&self) -> u32 {
+        3
+    }
+
+    fn length(&self) -> usize {
+        3
+    }
+}
+
+#[derive(Clone)]
+pub struct MaxLengthModel;
+
+impl max_length::Model for MaxLengthModel {
+    type B = u32;
+    type Symbol = Symbol;
+    type ValueError = Infallible;
+
+    fn probability(&self, symbol: Option<&Self::Symbol>) -> Result<Range<u32>, Self::ValueError> {
+        match symbol {
+            Some(Symbol::A) => Ok(0..1),
+            Some(Symbol::B) => Ok(1..2),
+            Some(Symbol::C) => Ok(2..3),
+            None => Ok(3..4),
+        }
+    }
+
+    fn symbol(&self, value: u32) -> Option<Self::Symbol> {
+        match value {
+            0..1 => Some(Symbol::A),
+            1..2 => Some(Symbol::B),
+            2..3 => Some(Symbol::C),
+            3..4 => None,
+            _ => unreachable!(),
+        }
+    }
+
+    fn max_denominator(&self) -> u32 {
+        4
+    }
+
+    fn max_length(&self) -> usize {
+        3
+    }
+}
+
+#[test]
+fn fixed_length_round_trip() {
+    round_trip(
+        fixed_length::Wrapper::new(FixedLengthModel),
+        &[Symbol::A, Symbol::B, Symbol::C],
+    );
+}
+
+#[test_case(&[] ; "empty")]
+#[test_case(&[Symbol::A, Symbol::B] ; "shorter")]
+#[test_case(&[Symbol::A, Symbol::B, Symbol::C] ; "exact")]
+fn max_length_round_trip(input: &[Symbol]) {
+    round_trip(max_length::Wrapper::new(MaxLengthModel), input);
+}
+
+#[test]
+fn fenwick_simple() {
+    let input = common::sherlock_symbols();
+    let model = simple::FenwickModel::<usize>::builder(256, 1 << 20).build();
+
+    round_trip(model, &input);
+}
+
+#[test]
+fn fenwick_context_switching() {
+    let input = common::sherlock_symbols();
+    let model = context_switching::FenwickModel::<usize>::with_symbols(256, 1 << 20);
+
+    round_trip(model, &input);
+}
+
+#[test]
+fn highly_skewed() {
+    let mut input = vec![3; 100_000];
+    input.extend([1, 2, 3, 1, 0, 0, 2]);
+    let model = simple::FenwickModel::<usize>::builder(4, 1 << 30).build();
+
+    round_trip(model, &input);
+}
+
+#[test]
+fn chain() {
+    let input1 = [Symbol::A, Symbol::B, Symbol::C];
+    let input2 = [Symbol::C, Symbol::A];
+
+    let mut buffer = Vec::new();
+    let mut encoder1 = ans::Encoder::new(fixed_length::Wrapper::new(FixedLengthModel), &mut buffer);
+    for symbol in &input1 {
+        encoder1.encode(Some(symbol)).unwrap();
+    }
+    encoder1.encode(None).unwrap();
+    let mut encoder2 = encoder1.chain(max_length::Wrapper::new(MaxLengthModel));
+    for symbol in &input2 {
+        encoder2.encode(Some(symbol)).unwrap();
+    }
+    encoder2.encode(None).unwrap();
+    encoder2.flush();
+
+    let mut decoder1 = ans::Decoder::new(fixed_length::Wrapper::new(FixedLengthModel), &buffer);
+    let output1: Vec<_> = decoder1.decode_all().map(Result::unwrap).collect();
+    let mut decoder2 = decoder1.chain(max_length::Wrapper::new(MaxLengthModel));
+    let output2: Vec<_> = decoder2.decode_all().map(Result::unwrap).collect();
+
+    assert_eq!(input1.as_slice(), output1.as_slice());
+    assert_eq!(input2.as_slice(), output2.as_slice());
+}
+
+#[test_case(&[] ; "empty")]
+#[test_case(&[0; 64] ; "zeros")]
+fn zero_state(input: &[u8]) {
+    let model = simple::FenwickModel::<usize>::builder(256, 1 << 10).build();
+    let mut decoder = ans::Decoder::new(model, input);
+
+    assert!(matches!(decoder.decode(), Err(DecodeError::CorruptStream)));
+}
@@ -0,0 +1,142 @@
+use arithmetic_coding::{
+    binary::{Decoder, Encoder, Prob},
+    range_coder,
+};
+use fenwick_model::simple::FenwickModel;
+use test_case::test_case;
+
+/// A deterministic sequence of bits, where each bit is `true` with
+/// probability `p_true / 256`.
+fn bits(len: usize, p_true: u64) -> Vec<bool> {
+    let mut seed: u64 = 0x2545_F491_4F6C_DD1D;
+    (0..len)
+        .map(|_| {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            (seed & 0xFF) < p_true
+        })
+        .collect()
+}
+
+fn round_trip<const BITS: u32, const SHIFT: u32>(input: &[bool]) -> usize {
+    let mut buffer = Vec::new();
+    let mut encoder = Encoder::new(&mut buffer);
+    let mut prob = Prob::<BITS, SHIFT>::new();
+    for &bit in input {
+        encoder.encode_bit(&mut prob, bit);
+    }
+    encoder.flush();
+
+    let mut decoder = Decoder::new(&buffer);
+    let mut prob = Prob::<BITS, SHIFT>::new();
+    let output: Vec<bool> = input
+        .iter()
+        .map(|_| decoder.decode_bit(&mut prob))
+        .collect();
+
+    assert_eq!(input, output.as_slice());
+    buffer.len()
+}
+
+#[test_case(0 ; "all false")]
+#[test_case(8 ; "mostly false")]
+#[test_case(128 ; "equiprobable")]
+#[test_case(250 ; "mostly true")]
+#[test_case(256 ; "all true")]
+fn round_trip_11_bit(p_true: u64) {
+    round_trip::<11, 5>(&bits(10_000, p_true));
+}
+
+#[test_case(0 ; "all false")]
+#[test_case(8 ; "mostly false")]
+#[test_case(128 ; "equiprobable")]
+#[test_case(250 ; "mostly true")]
+#[test_case(256 ; "all true")]
+fn round_trip_12_bit(p_true: u64) {
+    round_trip::<12, 4>(&bits(10_000, p_true));
+}
+
+#[test]
+fn skewed_input_compresses() {
+    let input = bits(80_000, 8);
+
+    // 80,000 bits is 10,000 bytes uncompressed. The entropy of this source is
+    // ~0.2 bits per symbol
+    let compressed = round_trip::<12, 5>(&input);
+    assert!(compressed < 2_500, "compressed size: {compressed}");
+}
+
+#[test]
+fn bypass() {
+    let input = bits(1_000, 128);
+
+    let mut buffer = Vec::new();
+    let mut encoder = Encoder::new(&mut buffer);
+    for &bit in &input {
+        encoder.encode_bypass(bit);
+    }
+    encoder.flush();
+
+    let mut decoder = Decoder::new(&buffer);
+    let output: Vec<bool> = input.iter().map(|_| decoder.decode_bypass()).collect();
+
+    assert_eq!(input, output);
+}
+
+#[test]
+fn mixed_with_range_coder() {
+    let flags = bits(100, 32);
+    let symbols = [3, 1, 4, 1, 5, 9, 2, 6];
+    let model = FenwickModel::builder(10, 1 << 20).build();
+
+    let mut buffer = Vec::new();
+    let mut encoder = Encoder::new(&mut buffer);
+    let mut prob = Prob::<11, 5>::new();
+    for &bit in &flags {
+        encoder.encode_bit(&mut prob, bit);
+    }
+    range_coder::Encoder::with_state(encoder.into_inner(), model.clone())
+        .encode_all(symbols)
+        .unwrap();
+
+    let mut decoder = Decoder::new(&buffer);
+    let mut prob = Prob::<11, 5>::new();
+    let output_flags: Vec<bool> = flags
+        .iter()
+        .map(|_| decoder.decode_bit(&mut prob))
+        .collect();
+    let mut decoder = range_coder::Decoder::with_state(decoder.into_inner(), model);
+    let output_symbols: Vec<usize> = decoder.decode_all().map(Result::unwrap).collect();
+
+    assert_eq!(flags, output_flags);
+    assert_eq!(symbols.as_slice(), output_symbols.as_slice());
+}
+
+#[test]
+fn prob_adapts() {
+    let mut prob = Prob::<11, 5>::new();
+    assert_eq!(prob.get(), 1024);
+
+    prob.update(false);
+    assert_eq!(prob.get(), 1056);
+
+    prob.update(true);
+    assert_eq!(prob.get(), 1023);
+
+    // the estimate saturates, but never reaches certainty
+    for _ in 0..1_000 {
+        prob.update(true);
+    }
+    assert_eq!(prob.get(), 31);
+    for _ in 0..1_000 {
+        prob.update(false);
+    }
+    assert_eq!(prob.get(), 2017);
+}
+
+#[test]
+#[should_panic(expected = "probability out of range")]
+fn prob_out_of_range() {
+    let _ = Prob::<11, 5>::with_probability(2048);
+}
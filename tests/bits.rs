@@ -0,0 +1,156 @@
+use arithmetic_coding::{
+    Decoder, Encoder, Error, Termination,
+    bits::{BitSink, BitSource, CapacityError, Rewind, SliceReader, SliceWriter, VecWriter},
+};
+use bitstream_io::{BigEndian, BitRead, BitReader, BitWrite, BitWriter};
+use fenwick_model::simple::FenwickModel;
+use test_case::test_case;
+
+mod common;
+
+fn model() -> FenwickModel {
+    FenwickModel::builder(256, 1 << 20).build()
+}
+
+/// A deterministic pseudo-random sequence of bits
+fn bits(len: usize) -> Vec<bool> {
+    let mut state = 0x2545_f491_4f6c_dd1d_u64;
+    (0..len)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state & 1 == 1
+        })
+        .collect()
+}
+
+#[test_case(0; "empty")]
+#[test_case(5; "partial byte")]
+#[test_case(64; "one word")]
+#[test_case(131; "several words")]
+fn vec_writer_matches_bitstream_io(len: usize) {
+    let bits = bits(len);
+
+    let mut writer = VecWriter::new();
+    let mut bitwriter = BitWriter::endian(Vec::new(), BigEndian);
+    for &bit in &bits {
+        writer.put_bit(bit).unwrap();
+        bitwriter.write_bit(bit).unwrap();
+    }
+    bitwriter.byte_align().unwrap();
+
+    assert_eq!(writer.finish(), bitwriter.into_writer());
+}
+
+#[test_case(0; "empty")]
+#[test_case(5; "partial byte")]
+#[test_case(64; "one word")]
+#[test_case(131; "several words")]
+fn slice_reader_matches_bitstream_io(len: usize) {
+    let mut writer = VecWriter::new();
+    for bit in bits(len) {
+        writer.put_bit(bit).unwrap();
+    }
+    let buffer = writer.finish();
+
+    let mut reader = SliceReader::new(&buffer);
+    let mut bitreader = BitReader::endian(buffer.as_slice(), BigEndian);
+    for _ in 0..buffer.len() * 8 {
+        assert_eq!(
+            reader.next_bit().unwrap(),
+            Some(bitreader.read_bit().unwrap())
+        );
+    }
+    assert_eq!(reader.next_bit().unwrap(), None);
+}
+
+#[test]
+fn round_trip() {
+    let input = common::sherlock_symbols();
+
+    let mut writer = VecWriter::new();
+    Encoder::new(model(), &mut writer)
+        .encode_all(input.iter().copied())
+        .unwrap();
+    let buffer = writer.finish();
+
+    let mut decoder = Decoder::new(model(), SliceReader::new(&buffer));
+    let output = decoder.decode_to_vec().unwrap();
+
+    assert_eq!(input, output);
+}
+
+#[test]
+fn slice_writer() {
+    let input = common::sherlock_symbols();
+    let mut buffer = vec![0; input.len()];
+
+    let mut writer = SliceWriter::new(&mut buffer);
+    Encoder::new(model(), &mut writer)
+        .encode_all(input.iter().copied())
+        .unwrap();
+    let len = writer.finish().unwrap();
+
+    let mut decoder = Decoder::new(model(), SliceReader::new(&buffer[..len]));
+    let output = decoder.decode_to_vec().unwrap();
+
+    assert_eq!(input, output);
+}
+
+#[test]
+fn slice_writer_capacity() {
+    let input = common::sherlock_symbols();
+    let mut buffer = [0; 100];
+
+    let mut writer = SliceWriter::new(&mut buffer);
+    let result = Encoder::new(model(), &mut writer).encode_all(input);
+
+    let Err(Error::Io(e)) = result else {
+        panic!("expected an I/O error");
+    };
+    let e = e.into_inner().unwrap().downcast::<CapacityError>().unwrap();
+    assert_eq!(e.capacity, 100);
+}
+
+#[test]
+fn rewind() {
+    let buffer = [0b1010_1100, 0b0101_0011];
+    let mut reader = SliceReader::new(&buffer);
+
+    for _ in 0..11 {
+        reader.next_bit().unwrap();
+    }
+    reader.rewind(6).unwrap();
+
+    assert_eq!(reader.position(), 5);
+    assert_eq!(reader.next_bit().unwrap(), Some(true));
+    assert_eq!(reader.next_bit().unwrap(), Some(false));
+    assert_eq!(reader.next_bit().unwrap(), Some(false));
+    assert_eq!(reader.next_bit().unwrap(), Some(false));
+}
+
+#[test]
+fn finish() {
+    let input: Vec<usize> = common::sherlock_symbols().into_iter().take(100).collect();
+
+    let mut writer = VecWriter::new();
+    Encoder::new(model(), &mut writer)
+        .termination(Termination::Minimal)
+        .encode_all(input.iter().copied())
+        .unwrap();
+    for bit in [true, true, false, true] {
+        writer.put_bit(bit).unwrap();
+    }
+    let buffer = writer.finish();
+
+    let mut decoder =
+        Decoder::new(model(), SliceReader::new(&buffer)).termination(Termination::Minimal);
+    assert_eq!(decoder.decode_to_vec().unwrap(), input);
+
+    let (mut reader, bits) = decoder.finish().unwrap();
+    assert_eq!(reader.position(), bits);
+    for bit in [true, true, false, true] {
+        assert_eq!(reader.next_bit().unwrap(), Some(bit));
+    }
+}
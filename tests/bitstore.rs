@@ -0,0 +1,129 @@
+use arithmetic_coding::BitStore;
+
+/// xorshift64
+struct Rng(u64);
+
+impl Rng {
+    const fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// A random number with a random number of bits, so that small values
+    /// are well represented
+    fn bits(&mut self, max_bits: u32) -> u64 {
+        let bits = u32::try_from(self.next() % u64::from(max_bits)).unwrap() + 1;
+        self.next() >> (u64::BITS - bits)
+    }
+}
+
+/// Check [`BitStore::mul_div_reciprocal`] against [`BitStore::mul_div`] for
+/// products whose quotient fits in `B`.
+fn check_reciprocal<B>(a: B, b: B, divisor: B)
+where
+    B: BitStore + Eq,
+{
+    let reciprocal = divisor.reciprocal();
+    assert_eq!(reciprocal.divisor(), divisor);
+    assert_eq!(
+        a.mul_div_reciprocal(b, reciprocal),
+        a.mul_div(b, divisor),
+        "{a:?} * {b:?} / {divisor:?}"
+    );
+}
+
+#[test]
+fn reciprocal_u32() {
+    let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+    for _ in 0..100_000 {
+        let divisor = u32::try_from(rng.bits(32)).unwrap().max(1);
+        let a = u32::try_from(rng.bits(32)).unwrap();
+        // b <= divisor, so the quotient fits
+        let b = u32::try_from(rng.next() % (u64::from(divisor) + 1)).unwrap();
+        check_reciprocal(a, b, divisor);
+    }
+}
+
+#[test]
+fn reciprocal_u64() {
+    let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+    for _ in 0..100_000 {
+        let divisor = rng.bits(64).max(1);
+        let a = rng.bits(64);
+        let b = rng.next() % divisor.saturating_add(1);
+        check_reciprocal(a, b, divisor);
+        check_reciprocal(a, divisor, divisor);
+    }
+}
+
+#[test]
+fn reciprocal_usize() {
+    let mut rng = Rng(0xdead_beef_cafe_f00d);
+    for _ in 0..10_000 {
+        let divisor = usize::try_from(rng.bits(usize::BITS)).unwrap().max(1);
+        let a = usize::try_from(rng.bits(usize::BITS)).unwrap();
+        let b = usize::try_from(rng.next()).unwrap() % divisor.saturating_add(1);
+        check_reciprocal(a, b, divisor);
+    }
+}
+
+#[test]
+fn reciprocal_edge_cases() {
+    for divisor in [
+        1,
+        2,
+        3,
+        u32::MAX / 2,
+        u32::MAX / 2 + 1,
+        u32::MAX - 1,
+        u32::MAX,
+    ] {
+        for a in [0, 1, divisor / 2, divisor, u32::MAX] {
+            for b in [0, 1, divisor / 2, divisor - 1, divisor] {
+                check_reciprocal(a, b, divisor);
+            }
+        }
+    }
+    for divisor in [1, 3, u64::MAX / 3, 1 << 63, u64::MAX] {
+        for a in [0, 1, u64::MAX] {
+            check_reciprocal(a, divisor, divisor);
+            check_reciprocal(a, divisor - 1, divisor);
+        }
+    }
+    check_reciprocal(u128::MAX / 7, 5, 6);
+}
+
+#[test]
+fn mul_shr() {
+    let mut rng = Rng(0x0123_4567_89ab_cdef);
+    for _ in 0..10_000 {
+        let shift = u32::try_from(rng.next() % 63).unwrap() + 1;
+        let a = rng.next();
+        let b = rng.next() >> (64 - shift);
+        assert_eq!(a.mul_shr(b, shift), a.mul_div(b, 1 << shift));
+    }
+}
+
+#[test]
+fn mul_div_ceil() {
+    assert_eq!(7_u32.mul_div_ceil(3, 7), 3);
+    assert_eq!(7_u32.mul_div_ceil(3, 5), 5);
+    assert_eq!(u64::MAX.mul_div_ceil(u64::MAX, u64::MAX), u64::MAX);
+}
+
+#[test]
+fn checked() {
+    fn check<B: BitStore + Eq>(max: B) {
+        assert_eq!(BitStore::checked_add(max - B::ONE, B::ONE), Some(max));
+        assert_eq!(BitStore::checked_add(max, B::ONE), None);
+        assert_eq!(BitStore::checked_mul(max, B::ONE), Some(max));
+        assert_eq!(BitStore::checked_mul(max, B::ONE + B::ONE), None);
+    }
+
+    check(u32::MAX);
+    check(u64::MAX);
+    check(u128::MAX);
+    check(usize::MAX);
+}
@@ -0,0 +1,202 @@
+use std::{convert::Infallible, ops::Range};
+
+use arithmetic_coding::{
+    DecodeError, Decoder, Encoder, Error, InvalidProbability, InvalidProbabilityKind, Model,
+    bits::{SliceReader, VecWriter},
+};
+
+/// A model for the symbols `0..4`, which can be made to misbehave.
+#[derive(Debug, Clone, Copy)]
+enum Faulty {
+    None,
+    EmptyRange(u32),
+    OutOfBounds(u32),
+    ZeroDenominator,
+    DenominatorTooLarge,
+    WrongDenominatorLog2,
+}
+
+impl Model for Faulty {
+    type B = u32;
+    type Symbol = u32;
+    type ValueError = Infallible;
+
+    fn probability(&self, symbol: Option<&u32>) -> Result<Range<u32>, Infallible> {
+        Ok(match (self, symbol) {
+            (Self::EmptyRange(bad), Some(&s)) if s == *bad => s..s,
+            (Self::OutOfBounds(bad), Some(&s)) if s == *bad => s..6,
+            (_, Some(&s)) => s..s + 1,
+            (_, None) => 4..5,
+        })
+    }
+
+    fn symbol(&self, value: u32) -> Option<u32> {
+        (value < 4).then_some(value)
+    }
+
+    fn max_denominator(&self) -> u32 {
+        5
+    }
+
+    fn denominator(&self) -> u32 {
+        match self {
+            Self::ZeroDenominator => 0,
+            Self::DenominatorTooLarge => 6,
+            _ => 5,
+        }
+    }
+
+    fn denominator_log2(&self) -> Option<u32> {
+        match self {
+            Self::WrongDenominatorLog2 => Some(2),
+            _ => None,
+        }
+    }
+}
+
+const INPUT: [u32; 8] = [0, 1, 2, 3, 3, 2, 1, 0];
+
+fn encode(model: Faulty) -> Result<Vec<u8>, Error<Infallible>> {
+    let mut writer = VecWriter::new();
+    Encoder::new(model, &mut writer)
+        .checked()
+        .encode_all(INPUT)?;
+    Ok(writer.finish())
+}
+
+fn decode(model: Faulty, input: &[u8]) -> Result<Vec<u32>, DecodeError<Infallible>> {
+    Decoder::new(model, SliceReader::new(input))
+        .checked()
+        .decode_to_vec()
+        .map_err(|e| e.error)
+}
+
+const fn invalid(index: u64, kind: InvalidProbabilityKind) -> InvalidProbability {
+    InvalidProbability { index, kind }
+}
+
+#[test]
+fn round_trip() {
+    let buffer = encode(Faulty::None).unwrap();
+
+    let mut writer = VecWriter::new();
+    Encoder::new(Faulty::None, &mut writer)
+        .encode_all(INPUT)
+        .unwrap();
+    assert_eq!(buffer, writer.finish());
+
+    assert_eq!(decode(Faulty::None, &buffer).unwrap(), INPUT);
+}
+
+#[test]
+fn encoder_errors() {
+    let cases = [
+        (
+            Faulty::EmptyRange(2),
+            invalid(2, InvalidProbabilityKind::EmptyRange),
+        ),
+        (
+            Faulty::OutOfBounds(3),
+            invalid(3, InvalidProbabilityKind::OutOfBounds),
+        ),
+        (
+            Faulty::ZeroDenominator,
+            invalid(0, InvalidProbabilityKind::ZeroDenominator),
+        ),
+        (
+            Faulty::DenominatorTooLarge,
+            invalid(0, InvalidProbabilityKind::DenominatorTooLarge),
+        ),
+        (
+            Faulty::WrongDenominatorLog2,
+            invalid(0, InvalidProbabilityKind::WrongDenominatorLog2),
+        ),
+    ];
+
+    for (model, expected) in cases {
+        match encode(model) {
+            Err(Error::InvalidProbability(e)) => assert_eq!(e, expected),
+            other => panic!("expected {expected:?}, got {other:?}"),
+        }
+    }
+}
+
+#[test]
+fn decoder_errors() {
+    let buffer = encode(Faulty::None).unwrap();
+
+    let cases = [
+        (
+            Faulty::EmptyRange(1),
+            invalid(1, InvalidProbabilityKind::EmptyRange),
+        ),
+        (
+            Faulty::OutOfBounds(3),
+            invalid(3, InvalidProbabilityKind::OutOfBounds),
+        ),
+        (
+            Faulty::ZeroDenominator,
+            invalid(0, InvalidProbabilityKind::ZeroDenominator),
+        ),
+        (
+            Faulty::DenominatorTooLarge,
+            invalid(0, InvalidProbabilityKind::DenominatorTooLarge),
+        ),
+        (
+            Faulty::WrongDenominatorLog2,
+            invalid(0, InvalidProbabilityKind::WrongDenominatorLog2),
+        ),
+    ];
+
+    for (model, expected) in cases {
+        match decode(model, &buffer) {
+            Err(DecodeError::InvalidProbability(e)) => assert_eq!(e, expected),
+            other => panic!("expected {expected:?}, got {other:?}"),
+        }
+    }
+}
+
+#[test]
+fn chained_index() {
+    let expected = invalid(2, InvalidProbabilityKind::OutOfBounds);
+
+    let mut writer = VecWriter::new();
+    let mut encoder = Encoder::new(Faulty::None, &mut writer).checked();
+    for symbol in &INPUT {
+        encoder.encode(Some(symbol)).unwrap();
+    }
+    encoder.encode(None).unwrap();
+    match encoder.chain(Faulty::OutOfBounds(2)).encode_all(INPUT) {
+        Err(Error::InvalidProbability(e)) => assert_eq!(e, expected),
+        other => panic!("expected {expected:?}, got {other:?}"),
+    }
+
+    let mut writer = VecWriter::new();
+    let mut encoder = Encoder::new(Faulty::None, &mut writer);
+    for symbol in &INPUT {
+        encoder.encode(Some(symbol)).unwrap();
+    }
+    encoder.encode(None).unwrap();
+    encoder.chain(Faulty::None).encode_all(INPUT).unwrap();
+    let buffer = writer.finish();
+
+    let mut decoder = Decoder::new(Faulty::None, SliceReader::new(&buffer)).checked();
+    assert_eq!(decoder.decode_to_vec().unwrap(), INPUT);
+    match decoder
+        .chain(Faulty::OutOfBounds(2))
+        .decode_to_vec()
+        .map_err(|e| e.error)
+    {
+        Err(DecodeError::InvalidProbability(e)) => assert_eq!(e, expected),
+        other => panic!("expected {expected:?}, got {other:?}"),
+    }
+}
+
+#[test]
+fn error_message() {
+    let error = invalid(3, InvalidProbabilityKind::EmptyRange);
+    assert_eq!(
+        error.to_string(),
+        "invalid probability for symbol 3: the probability range is empty"
+    );
+}
@@ -0,0 +1,96 @@
+use arithmetic_coding::{
+    Decoder, Encoder,
+    bits::{BitSink, SliceReader, SliceWriter, Truncate, VecWriter},
+};
+use fenwick_model::context_switching::FenwickModel;
+
+mod common;
+
+fn model() -> FenwickModel {
+    FenwickModel::with_symbols(256, 1 << 20)
+}
+
+#[test]
+fn rollback() {
+    let input = &common::sherlock_symbols()[..2000];
+    let (prefix, suffix) = input.split_at(1000);
+
+    let mut writer = VecWriter::new();
+    let mut encoder = Encoder::new(model(), &mut writer);
+    for symbol in prefix {
+        encoder.encode(Some(symbol)).unwrap();
+    }
+
+    let checkpoint = encoder.checkpoint();
+    let start = encoder.position();
+
+    // try out a few alternatives, and undo them
+    for alternative in [&[1, 2, 3][..], &[255; 100], &suffix[..500]] {
+        for symbol in alternative {
+            encoder.encode(Some(symbol)).unwrap();
+        }
+        assert!(encoder.position() >= start);
+        encoder.rollback(&checkpoint).unwrap();
+        assert_eq!(encoder.position(), start);
+    }
+
+    encoder.encode_all(suffix.iter().copied()).unwrap();
+    let buffer = writer.finish();
+
+    assert_eq!(buffer, common::encode(model(), input));
+
+    let mut decoder = Decoder::new(model(), SliceReader::new(&buffer));
+    assert_eq!(decoder.decode_to_vec().unwrap(), input);
+}
+
+#[test]
+fn rollback_slice_writer() {
+    let input = &common::sherlock_symbols()[..2000];
+    let (prefix, suffix) = input.split_at(1000);
+
+    let mut buffer = vec![0; input.len()];
+    let mut writer = SliceWriter::new(&mut buffer);
+    let mut encoder = Encoder::new(model(), &mut writer);
+    for symbol in prefix {
+        encoder.encode(Some(symbol)).unwrap();
+    }
+
+    let checkpoint = encoder.checkpoint();
+    for symbol in prefix {
+        encoder.encode(Some(symbol)).unwrap();
+    }
+    encoder.rollback(&checkpoint).unwrap();
+
+    encoder.encode_all(suffix.iter().copied()).unwrap();
+    let len = writer.finish().unwrap();
+
+    assert_eq!(buffer[..len], common::encode(model(), input));
+}
+
+#[test]
+fn truncate() {
+    let bits: Vec<bool> = (0..200u32).map(|i| i.count_ones() % 3 == 0).collect();
+
+    for position in [0, 1, 7, 8, 63, 64, 65, 130, 200] {
+        let mut writer = VecWriter::new();
+        for &bit in &bits {
+            writer.put_bit(bit).unwrap();
+        }
+        writer.truncate(position).unwrap();
+        assert_eq!(writer.position(), position);
+
+        let mut expected = VecWriter::new();
+        for &bit in &bits[..usize::try_from(position).unwrap()] {
+            expected.put_bit(bit).unwrap();
+        }
+
+        assert_eq!(writer.finish(), expected.finish());
+    }
+}
+
+#[test]
+fn truncate_beyond_end() {
+    let mut writer = VecWriter::new();
+    writer.put_bit(true).unwrap();
+    assert!(writer.truncate(2).is_err());
+}
@@ -1,36 +1,60 @@
-use arithmetic_coding::{Decoder, Encoder, Model};
-use bitstream_io::{BigEndian, BitReader, BitWrite, BitWriter};
+// each test crate only uses some of these helpers
+#![allow(dead_code)]
 
-pub fn round_trip<M>(model: M, input: &[M::Symbol])
+use std::{fs::File, io::Read};
+
+use arithmetic_coding::{
+    Decoder, Encoder, Model,
+    bits::{SliceReader, VecWriter},
+};
+
+/// The text of 'The Adventures of Sherlock Holmes'
+pub fn sherlock() -> String {
+    let mut input = String::new();
+    File::open("./resources/sherlock.txt")
+        .unwrap()
+        .read_to_string(&mut input)
+        .unwrap();
+    input
+}
+
+/// The bytes of [`sherlock`], as symbols of a byte model
+pub fn sherlock_symbols() -> Vec<usize> {
+    sherlock().bytes().map(usize::from).collect()
+}
+
+/// Encode and decode the input, and return the size of the encoded stream in
+/// bytes.
+pub fn round_trip<M>(model: M, input: &[M::Symbol]) -> usize
 where
     M: Model + Clone,
     M::Symbol: PartialEq + std::fmt::Debug + Clone,
 {
-    let buffer = encode(model.clone(), input.to_owned());
+    let buffer = encode(model.clone(), input);
     let output = decode(model, &buffer);
 
     assert_eq!(input, output.as_slice());
+    buffer.len()
 }
 
-fn encode<M>(model: M, input: Vec<M::Symbol>) -> Vec<u8>
+pub fn encode<M>(model: M, input: &[M::Symbol]) -> Vec<u8>
 where
     M: Model,
+    M::Symbol: Clone,
 {
-    let mut bitwriter = BitWriter::endian(Vec::new(), BigEndian);
-    let encoder = Encoder::new(model, &mut bitwriter);
-
-    encoder.encode_all(input).unwrap();
-    bitwriter.byte_align().unwrap();
-
-    bitwriter.into_writer()
+    let mut writer = VecWriter::new();
+    Encoder::new(model, &mut writer)
+        .encode_all(input.iter().cloned())
+        .unwrap();
+    writer.finish()
 }
 
-fn decode<M>(model: M, buffer: &[u8]) -> Vec<M::Symbol>
+pub fn decode<M>(model: M, buffer: &[u8]) -> Vec<M::Symbol>
 where
     M: Model,
 {
-    let bitreader = BitReader::endian(buffer, BigEndian);
-    let mut decoder = Decoder::new(model, bitreader);
-
-    decoder.decode_all().map(Result::unwrap).collect()
+    Decoder::new(model, SliceReader::new(buffer))
+        .decode_all()
+        .map(Result::unwrap)
+        .collect()
 }
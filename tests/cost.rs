@@ -0,0 +1,131 @@
+use arithmetic_coding::{
+    Encoder, Model,
+    bits::BitCounter,
+    cost::{self, Cost},
+};
+use fenwick_model::{context_switching, simple};
+
+mod common;
+
+fn encoded_bits<M: Model>(model: M, input: &[M::Symbol]) -> u64
+where
+    M::Symbol: Clone,
+{
+    let mut counter = BitCounter::new();
+    Encoder::new(model, &mut counter)
+        .encode_all(input.iter().cloned())
+        .unwrap();
+    counter.bits()
+}
+
+#[test]
+fn exact_bits() {
+    let input = common::sherlock_symbols();
+
+    let cost = cost::estimate(
+        context_switching::FenwickModel::<usize>::with_symbols(256, 1 << 20),
+        input.iter().copied(),
+    )
+    .unwrap();
+
+    assert_eq!(cost.symbols, input.len());
+    assert_eq!(
+        cost.bits,
+        encoded_bits(
+            context_switching::FenwickModel::<usize>::with_symbols(256, 1 << 20),
+            &input
+        )
+    );
+
+    // the byte-aligned output is the exact bit count, rounded up
+    let buffer = common::encode(
+        context_switching::FenwickModel::<usize>::with_symbols(256, 1 << 20),
+        &input,
+    );
+    assert_eq!(buffer.len() as u64, cost.bits.div_ceil(8));
+}
+
+#[test]
+fn ideal_bits() {
+    let input = common::sherlock_symbols();
+
+    let cost = cost::estimate(
+        simple::FenwickModel::<usize>::builder(256, 1 << 20).build(),
+        input.iter().copied(),
+    )
+    .unwrap();
+
+    // arithmetic coding is within a few bits of the ideal
+    let bits = f64::from(u32::try_from(cost.bits).unwrap());
+    assert!(cost.ideal_bits <= bits + 2.0);
+    assert!(bits - cost.ideal_bits < cost.ideal_bits / 1000.0 + 64.0);
+}
+
+#[test]
+fn compare_models() {
+    let input = common::sherlock_symbols();
+
+    let adaptive = cost::estimate(
+        context_switching::FenwickModel::<usize>::with_symbols(256, 1 << 20),
+        input.iter().copied(),
+    )
+    .unwrap();
+    let order0 = cost::estimate(
+        simple::FenwickModel::<usize>::builder(256, 1 << 20).build(),
+        input.iter().copied(),
+    )
+    .unwrap();
+
+    assert!(adaptive.bits < order0.bits);
+}
+
+#[test]
+fn empty_input() {
+    let cost = cost::estimate(
+        simple::FenwickModel::<usize>::builder(256, 1 << 20).build(),
+        std::iter::empty(),
+    )
+    .unwrap();
+
+    assert_eq!(cost.symbols, 0);
+    assert!(cost.bits > 0);
+    assert!(cost.ideal_bits > 0.0);
+}
+
+#[test]
+fn sum() {
+    let input = common::sherlock_symbols();
+    let chunks: Vec<Cost> = input
+        .chunks(10_000)
+        .map(|chunk| {
+            cost::estimate(
+                simple::FenwickModel::<usize>::builder(256, 1 << 20).build(),
+                chunk.iter().copied(),
+            )
+            .unwrap()
+        })
+        .collect();
+
+    let total: Cost = chunks.iter().copied().sum();
+    assert_eq!(total.symbols, input.len());
+    assert_eq!(total.bits, chunks.iter().map(|cost| cost.bits).sum::<u64>());
+}
+
+#[test]
+fn symbol_bits_uniform() {
+    let model = simple::FenwickModel::<usize>::builder(255, 1 << 20).build();
+
+    // 255 symbols plus EOF, all equally likely
+    for symbol in [0, 1, 100, 254] {
+        let bits = cost::symbol_bits(&model, Some(&symbol)).unwrap();
+        assert!((bits - 8.0).abs() < 1e-9);
+    }
+    assert!((cost::symbol_bits(&model, None).unwrap() - 8.0).abs() < 1e-9);
+}
+
+#[test]
+fn symbol_bits_invalid_symbol() {
+    let model = simple::FenwickModel::<usize>::builder(4, 1 << 20).build();
+    assert!(cost::symbol_bits(&model, Some(&4)).is_err());
+    assert!(cost::estimate(model, [1, 2, 9]).is_err());
+}
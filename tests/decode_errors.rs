@@ -0,0 +1,191 @@
+use std::ops::Range;
+
+use arithmetic_coding::{DecodeError, Decoder, Encoder, Model, ans, range_coder};
+use bitstream_io::{BigEndian, BitReader, BitWrite, BitWriter};
+
+#[derive(Debug, thiserror::Error)]
+#[error("invalid symbol: {0}")]
+pub struct Error(u8);
+
+/// A model for the integers 1..4
+#[derive(Clone)]
+pub struct GoodModel;
+
+impl Model for GoodModel {
+    type B = u32;
+    type Symbol = u8;
+    type ValueError = Error;
+
+    fn probability(&self, symbol: Option<&Self::Symbol>) -> Result<Range<u32>, Error> {
+        match symbol {
+            None => Ok(0..1),
+            Some(&1) => Ok(1..2),
+            Some(&2) => Ok(2..3),
+            Some(&3) => Ok(3..4),
+            Some(x) => Err(Error(*x)),
+        }
+    }
+
+    fn symbol(&self, value: u32) -> Option<Self::Symbol> {
+        match value {
+            0..1 => None,
+            1..2 => Some(1),
+            2..3 => Some(2),
+            3..4 => Some(3),
+            _ => unreachable!(),
+        }
+    }
+
+    fn max_denominator(&self) -> u32 {
+        4
+    }
+}
+
+/// A model whose [`Model::symbol`] method returns a symbol which is
+/// inconsistent with [`Model::probability`]
+#[derive(Clone)]
+pub struct InconsistentModel;
+
+impl Model for InconsistentModel {
+    type B = u32;
+    type Symbol = u8;
+    type ValueError = Error;
+
+    fn probability(&self, symbol: Option<&Self::Symbol>) -> Result<Range<u32>, Error> {
+        GoodModel.probability(symbol)
+    }
+
+    fn symbol(&self, value: u32) -> Option<Self::Symbol> {
+        // symbols 2 and 3 are swapped
+        match value {
+            2..3 => Some(3),
+            3..4 => Some(2),
+            _ => GoodModel.symbol(value),
+        }
+    }
+
+    fn max_denominator(&self) -> u32 {
+        4
+    }
+}
+
+/// A model which rejects one of the symbols returned by [`Model::symbol`]
+#[derive(Clone)]
+pub struct RejectingModel;
+
+impl Model for RejectingModel {
+    type B = u32;
+    type Symbol = u8;
+    type ValueError = Error;
+
+    fn probability(&self, symbol: Option<&Self::Symbol>) -> Result<Range<u32>, Error> {
+        match symbol {
+            Some(&3) => Err(Error(3)),
+            _ => GoodModel.probability(symbol),
+        }
+    }
+
+    fn symbol(&self, value: u32) -> Option<Self::Symbol> {
+        GoodModel.symbol(value)
+    }
+
+    fn max_denominator(&self) -> u32 {
+        4
+    }
+}
+
+const INPUT: &[u8] = &[1, 2, 3, 1];
+
+fn decode<M: Model>(model: M) -> Result<Vec<M::Symbol>, DecodeError<M::ValueError>> {
+    let mut bitwriter = BitWriter::endian(Vec::new(), BigEndian);
+    Encoder::new(GoodModel, &mut bitwriter)
+        .encode_all(INPUT.iter().copied())
+        .unwrap();
+    bitwriter.byte_align().unwrap();
+    let buffer = bitwriter.into_writer();
+
+    let mut decoder = Decoder::new(model, BitReader::endian(buffer.as_slice(), BigEndian));
+    decoder.decode_all().collect()
+}
+
+fn decode_range_coder<M: Model>(model: M) -> Result<Vec<M::Symbol>, DecodeError<M::ValueError>> {
+    let mut buffer = Vec::new();
+    range_coder::Encoder::new(GoodModel, &mut buffer)
+        .encode_all(INPUT.iter().copied())
+        .unwrap();
+
+    let mut decoder = range_coder::Decoder::new(model, &buffer);
+    decoder.decode_all().collect()
+}
+
+fn decode_ans<M: Model>(model: M) -> Result<Vec<M::Symbol>, DecodeError<M::ValueError>> {
+    let mut buffer = Vec::new();
+    ans::Encoder::new(GoodModel, &mut buffer)
+        .encode_all(INPUT.iter().copied())
+        .unwrap();
+
+    let mut decoder = ans::Decoder::new(model, &buffer);
+    decoder.decode_all().collect()
+}
+
+#[test]
+fn ok() {
+    assert_eq!(decode(GoodModel).unwrap(), INPUT);
+    assert_eq!(decode_range_coder(GoodModel).unwrap(), INPUT);
+    assert_eq!(decode_ans(GoodModel).unwrap(), INPUT);
+}
+
+#[test]
+fn corrupt_stream() {
+    assert!(matches!(
+        decode(InconsistentModel),
+        Err(DecodeError::CorruptStream)
+    ));
+    assert!(matches!(
+        decode_range_coder(InconsistentModel),
+        Err(DecodeError::CorruptStream)
+    ));
+    assert!(matches!(
+        decode_ans(InconsistentModel),
+        Err(DecodeError::CorruptStream)
+    ));
+}
+
+#[test]
+fn value_error() {
+    assert!(matches!(
+        decode(RejectingModel),
+        Err(DecodeError::ValueError(Error(3)))
+    ));
+    assert!(matches!(
+        decode_range_coder(RejectingModel),
+        Err(DecodeError::ValueError(Error(3)))
+    ));
+    assert!(matches!(
+        decode_ans(RejectingModel),
+        Err(DecodeError::ValueError(Error(3)))
+    ));
+}
+
+#[test]
+fn chained_decoder_surfaces_errors() {
+    let mut bitwriter = BitWriter::endian(Vec::new(), BigEndian);
+    let mut encoder = Encoder::new(GoodModel, &mut bitwriter);
+    for symbol in INPUT {
+        encoder.encode(Some(symbol)).unwrap();
+    }
+    encoder.encode(None).unwrap();
+    encoder.chain(GoodModel).encode_all([3]).unwrap();
+    bitwriter.byte_align().unwrap();
+    let buffer = bitwriter.into_writer();
+
+    let mut decoder = Decoder::new(GoodModel, BitReader::endian(buffer.as_slice(), BigEndian));
+    let output: Vec<u8> = decoder.decode_all().map(Result::unwrap).collect();
+    assert_eq!(output, INPUT);
+
+    let mut decoder = decoder.chain(RejectingModel);
+    assert!(matches!(
+        decoder.decode(),
+        Err(DecodeError::ValueError(Error(3)))
+    ));
+}
@@ -0,0 +1,172 @@
+use std::{convert::Infallible, ops::Range};
+
+use arithmetic_coding::{
+    Decoder, Encoder, Model,
+    bits::{SliceReader, VecWriter},
+    interleaved, precision_range, range_coder,
+};
+use test_case::test_case;
+
+mod common;
+
+/// A non-adaptive model of bytes, with frequencies taken from a sample of
+/// text and scaled to a fixed denominator.
+#[derive(Debug, Clone)]
+struct StaticModel {
+    cumulative: Vec<u32>,
+    denominator_log2: Option<u32>,
+}
+
+impl StaticModel {
+    /// The last symbol is EOF
+    const SYMBOLS: u32 = 257;
+
+    fn new(sample: &[u8], denominator: u32) -> Self {
+        let mut counts = vec![0_u64; 257];
+        for &byte in sample {
+            counts[usize::from(byte)] += 1;
+        }
+        let total: u64 = counts.iter().sum();
+        let spare = u64::from(denominator - Self::SYMBOLS);
+
+        // every symbol needs a frequency of at least one
+        let mut frequencies: Vec<u32> = counts
+            .iter()
+            .map(|&count| 1 + u32::try_from(count * spare / total).unwrap())
+            .collect();
+        let remainder = denominator - frequencies.iter().sum::<u32>();
+        *frequencies.iter_mut().max().unwrap() += remainder;
+
+        let mut cumulative = vec![0];
+        for frequency in frequencies {
+            cumulative.push(cumulative.last().unwrap() + frequency);
+        }
+
+        Self {
+            cumulative,
+            denominator_log2: None,
+        }
+    }
+
+    fn power_of_two(sample: &[u8], denominator_log2: u32) -> Self {
+        Self {
+            denominator_log2: Some(denominator_log2),
+            ..Self::new(sample, 1 << denominator_log2)
+        }
+    }
+}
+
+impl Model for StaticModel {
+    type B = u32;
+    type Symbol = u8;
+    type ValueError = Infallible;
+
+    fn probability(&self, symbol: Option<&u8>) -> Result<Range<u32>, Infallible> {
+        let index = symbol.map_or(256, |&byte| usize::from(byte));
+        Ok(self.cumulative[index]..self.cumulative[index + 1])
+    }
+
+    fn symbol(&self, value: u32) -> Option<u8> {
+        let index = self.cumulative.partition_point(|&c| c <= value) - 1;
+        u8::try_from(index).ok()
+    }
+
+    fn max_denominator(&self) -> u32 {
+        *self.cumulative.last().unwrap()
+    }
+
+    fn denominator_log2(&self) -> Option<u32> {
+        self.denominator_log2
+    }
+}
+
+/// The largest precision, since the default leaves too few bits for the
+/// larger denominators in a `u32`
+fn precision<M: Model>(model: &M) -> u32 {
+    *precision_range(model).unwrap().end()
+}
+
+fn encode<M: Model<Symbol = u8>>(model: M, input: &[u8]) -> Vec<u8> {
+    let mut writer = VecWriter::new();
+    let precision = precision(&model);
+    Encoder::with_precision(model, &mut writer, precision)
+        .encode_all(input.iter().copied())
+        .unwrap();
+    writer.finish()
+}
+
+fn decode<M: Model<Symbol = u8>>(model: M, buffer: &[u8]) -> Vec<u8> {
+    let precision = precision(&model);
+    Decoder::with_precision(model, SliceReader::new(buffer), precision)
+        .decode_all()
+        .map(Result::unwrap)
+        .collect()
+}
+
+#[test_case(12; "small")]
+#[test_case(16; "medium")]
+#[test_case(24; "large")]
+fn shift_matches_division(denominator_log2: u32) {
+    let input = common::sherlock().into_bytes();
+    let shift = StaticModel::power_of_two(&input, denominator_log2);
+    let division = StaticModel::new(&input, 1 << denominator_log2);
+
+    let buffer = encode(shift.clone(), &input);
+    assert_eq!(buffer, encode(division.clone(), &input));
+    assert_eq!(decode(shift, &buffer), input);
+    assert_eq!(decode(division, &buffer), input);
+}
+
+#[test]
+fn shift_default_precision() {
+    let input = common::sherlock().into_bytes();
+    common::round_trip(StaticModel::power_of_two(&input, 12), &input);
+}
+
+#[test_case(300; "just above the alphabet")]
+#[test_case(4000; "medium")]
+#[test_case(12_345_678; "large")]
+fn arbitrary_denominator(denominator: u32) {
+    let input = common::sherlock().into_bytes();
+    let model = StaticModel::new(&input, denominator);
+
+    let buffer = encode(model.clone(), &input);
+    assert_eq!(decode(model, &buffer), input);
+}
+
+#[test]
+fn interleaved() {
+    let input = common::sherlock().into_bytes();
+    let model = StaticModel::power_of_two(&input, 12);
+
+    let mut buffer = Vec::new();
+    interleaved::Encoder::<_, 4>::new(model.clone(), &mut buffer)
+        .encode_all(input.iter().copied())
+        .unwrap();
+
+    let mut decoder = interleaved::Decoder::<_, 4>::new(model, &buffer);
+    let output: Vec<_> = decoder.decode_all().map(Result::unwrap).collect();
+    assert_eq!(output, input);
+}
+
+#[test]
+fn range_coder_shift_matches_division() {
+    let input = common::sherlock().into_bytes();
+    let shift = StaticModel::power_of_two(&input, 12);
+    let division = StaticModel::new(&input, 1 << 12);
+
+    let mut buffer = Vec::new();
+    range_coder::Encoder::new(shift.clone(), &mut buffer)
+        .encode_all(input.iter().copied())
+        .unwrap();
+
+    let mut expected = Vec::new();
+    range_coder::Encoder::new(division, &mut expected)
+        .encode_all(input.iter().copied())
+        .unwrap();
+    assert_eq!(buffer, expected);
+
+    let mut decoder = range_coder::Decoder::new(shift, &buffer);
+    let output: Vec<_> = decoder.decode_all().map(Result::unwrap).collect();
+    assert_eq!(output, input);
+}
@@ -1,6 +1,6 @@
 use std::{convert::Infallible, ops::Range};
 
-use arithmetic_coding::fixed_length;
+use arithmetic_coding::{Model, fixed_length};
 
 mod common;
 
@@ -52,6 +52,17 @@ fn round_trip() {
     common::round_trip(fixed_length::Wrapper::new(MyModel), input);
 }
 
+#[test]
+fn decode_symbol() {
+    let mut model = fixed_length::Wrapper::new(MyModel);
+    assert_eq!(model.decode_symbol(1).unwrap(), (Some(Symbol::B), 1..2));
+
+    for symbol in [Symbol::A, Symbol::B, Symbol::C] {
+        model.update(Some(&symbol));
+    }
+    assert_eq!(model.decode_symbol(1).unwrap(), (None, 0..3));
+}
+
 #[test]
 #[should_panic(expected = "UnexpectedSymbol")]
 fn round_trip_fail() {
@@ -0,0 +1,80 @@
+use arithmetic_coding::Model;
+use fenwick_model::{Adaptation, Symbol, context_switching, simple, windowed};
+
+mod common;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Base {
+    A,
+    C,
+    G,
+    T,
+}
+
+impl Symbol for Base {
+    fn index(&self) -> usize {
+        *self as usize
+    }
+
+    fn from_index(index: usize) -> Option<Self> {
+        [Self::A, Self::C, Self::G, Self::T].get(index).copied()
+    }
+}
+
+#[test]
+fn bytes() {
+    let input = common::sherlock().into_bytes();
+    common::round_trip(
+        simple::FenwickModel::<u8, u32>::builder(256, 1 << 12).build(),
+        &input,
+    );
+    common::round_trip(
+        context_switching::FenwickModel::<u8, u32>::with_symbols(256, 1 << 12),
+        &input,
+    );
+    common::round_trip(
+        windowed::FenwickModel::<u8, u32>::builder(256, 4096).build(),
+        &input[..100_000],
+    );
+}
+
+#[test]
+fn chars() {
+    let input: Vec<char> = common::sherlock().chars().collect();
+    common::round_trip(
+        simple::FenwickModel::<char>::builder(256, 1 << 20).build(),
+        &input,
+    );
+}
+
+#[test]
+fn enums() {
+    let input: Vec<Base> = common::sherlock()
+        .bytes()
+        .map(|byte| [Base::A, Base::C, Base::G, Base::T][usize::from(byte % 4)])
+        .collect();
+    common::round_trip(
+        simple::FenwickModel::<Base, u128>::builder(4, 1 << 40)
+            .adaptation(Adaptation::new().increment(1 << 20))
+            .build(),
+        &input,
+    );
+}
+
+#[test]
+fn symbol_outside_alphabet() {
+    let model = simple::FenwickModel::<char>::builder(128, 1 << 20).build();
+    assert!(model.probability(Some(&'a')).is_ok());
+    let error = model.probability(Some(&'é')).unwrap_err();
+    assert_eq!(error.0, 'é');
+    assert_eq!(error.to_string(), "invalid symbol received: 'é'");
+
+    let model = context_switching::FenwickModel::<u8>::with_symbols(10, 1 << 20);
+    assert!(model.probability(Some(&10)).is_err());
+}
+
+#[test]
+#[should_panic(expected = "there are fewer than 5 symbols of this type")]
+fn alphabet_too_large() {
+    let _ = simple::FenwickModel::<Base>::builder(5, 1 << 20);
+}
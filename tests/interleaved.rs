@@ -0,0 +1,111 @@
+use arithmetic_coding::interleaved;
+use fenwick_model::{context_switching, simple};
+use test_case::test_case;
+
+mod common;
+
+fn round_trip<const N: usize>(input: &[usize]) {
+    let model = simple::FenwickModel::<usize>::builder(256, 1 << 20).build();
+
+    let mut buffer = Vec::new();
+    interleaved::Encoder::<_, N>::new(model.clone(), &mut buffer)
+        .encode_all(input.iter().copied())
+        .unwrap();
+
+    let mut decoder = interleaved::Decoder::<_, N>::new(model, &buffer);
+    let output: Vec<usize> = decoder.decode_all().map(Result::unwrap).collect();
+
+    assert_eq!(input, output.as_slice());
+}
+
+#[test_case(0; "empty")]
+#[test_case(1; "one symbol")]
+#[test_case(3; "fewer symbols than lanes")]
+#[test_case(1000; "many symbols")]
+fn fenwick_simple(len: usize) {
+    let input: Vec<usize> = common::sherlock_symbols().into_iter().take(len).collect();
+
+    round_trip::<1>(&input);
+    round_trip::<2>(&input);
+    round_trip::<4>(&input);
+    round_trip::<7>(&input);
+}
+
+#[test]
+fn fenwick_context_switching() {
+    let input = common::sherlock_symbols();
+    let model = context_switching::FenwickModel::<usize>::with_symbols(256, 1 << 20);
+
+    let mut buffer = Vec::new();
+    interleaved::Encoder::<_, 4>::new(model.clone(), &mut buffer)
+        .encode_all(input.iter().copied())
+        .unwrap();
+
+    let mut decoder = interleaved::Decoder::<_, 4>::new(model, &buffer);
+    let output: Vec<usize> = decoder.decode_all().map(Result::unwrap).collect();
+
+    assert_eq!(input, output);
+}
+
+#[test]
+fn single_lane_matches_bitwise_encoder() {
+    let input: Vec<usize> = common::sherlock_symbols().into_iter().take(1000).collect();
+    let model = simple::FenwickModel::<usize>::builder(256, 1 << 20).build();
+
+    let mut buffer = Vec::new();
+    interleaved::Encoder::<_, 1>::new(model.clone(), &mut buffer)
+        .encode_all(input.iter().copied())
+        .unwrap();
+
+    assert_eq!(buffer, common::encode(model, &input));
+}
+
+#[test]
+fn header() {
+    let input: Vec<usize> = common::sherlock_symbols().into_iter().take(1000).collect();
+    let model = simple::FenwickModel::<usize>::builder(256, 1 << 20).build();
+
+    let mut buffer = Vec::new();
+    interleaved::Encoder::<_, 3>::new(model, &mut buffer)
+        .encode_all(input)
+        .unwrap();
+
+    let lengths: Vec<usize> = buffer[..8]
+        .chunks_exact(4)
+        .map(|length| u32::from_le_bytes(length.try_into().unwrap()) as usize)
+        .collect();
+
+    // the last lane occupies the remainder of the stream
+    assert!(lengths.iter().sum::<usize>() < buffer.len() - 8);
+}
+
+#[test]
+fn chain() {
+    let first: Vec<usize> = common::sherlock_symbols().into_iter().take(100).collect();
+    let second: Vec<usize> = (0..10).collect();
+
+    let mut buffer = Vec::new();
+    let mut encoder = interleaved::Encoder::<_, 4>::new(
+        simple::FenwickModel::<usize>::builder(256, 1 << 20).build(),
+        &mut buffer,
+    );
+    for symbol in &first {
+        encoder.encode(Some(symbol)).unwrap();
+    }
+    encoder.encode(None).unwrap();
+    encoder
+        .chain(simple::FenwickModel::<usize>::builder(10, 1 << 20).build())
+        .encode_all(second.iter().copied())
+        .unwrap();
+
+    let mut decoder = interleaved::Decoder::<_, 4>::new(
+        simple::FenwickModel::<usize>::builder(256, 1 << 20).build(),
+        &buffer,
+    );
+    let output: Vec<usize> = decoder.decode_all().map(Result::unwrap).collect();
+    assert_eq!(output, first);
+
+    let mut decoder = decoder.chain(simple::FenwickModel::<usize>::builder(10, 1 << 20).build());
+    let output: Vec<usize> = decoder.decode_all().map(Result::unwrap).collect();
+    assert_eq!(output, second);
+}
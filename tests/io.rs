@@ -0,0 +1,184 @@
+use std::{
+    io::{self, BufRead, BufReader, Read, Write},
+    ops::Range,
+};
+
+use arithmetic_coding::{
+    Decoder, Model,
+    io::{ArithmeticReader, ArithmeticWriter},
+};
+use bitstream_io::{BigEndian, BitReader};
+use fenwick_model::{ValueError, simple::FenwickModel};
+
+mod common;
+
+#[derive(Debug, Clone)]
+pub struct ByteModel(FenwickModel);
+
+impl ByteModel {
+    fn new() -> Self {
+        Self(FenwickModel::builder(256, 1 << 20).build())
+    }
+
+    /// A model which rejects bytes outside the ASCII range
+    fn ascii() -> Self {
+        Self(FenwickModel::builder(128, 1 << 20).build())
+    }
+}
+
+impl Model for ByteModel {
+    type B = u64;
+    type Symbol = u8;
+    type ValueError = ValueError;
+
+    fn probability(&self, symbol: Option<&u8>) -> Result<Range<u64>, ValueError> {
+        self.0
+            .probability(symbol.map(|&byte| usize::from(byte)).as_ref())
+    }
+
+    fn symbol(&self, value: u64) -> Option<u8> {
+        self.0.symbol(value).map(|x| u8::try_from(x).unwrap())
+    }
+
+    fn max_denominator(&self) -> u64 {
+        self.0.max_denominator()
+    }
+
+    fn denominator(&self) -> u64 {
+        self.0.denominator()
+    }
+
+    fn update(&mut self, symbol: Option<&u8>) {
+        self.0
+            .update(symbol.map(|&byte| usize::from(byte)).as_ref());
+    }
+}
+
+fn compress(input: &[u8]) -> Vec<u8> {
+    let mut writer = ArithmeticWriter::new(ByteModel::new(), Vec::new());
+    for chunk in input.chunks(1000) {
+        writer.write_all(chunk).unwrap();
+    }
+    writer.finish().unwrap()
+}
+
+#[test]
+fn round_trip() {
+    let input = common::sherlock();
+    let buffer = compress(input.as_bytes());
+    assert!(buffer.len() < input.len());
+
+    let mut reader = ArithmeticReader::new(ByteModel::new(), buffer.as_slice());
+    let mut output = String::new();
+    reader.read_to_string(&mut output).unwrap();
+
+    assert_eq!(input, output);
+}
+
+#[test]
+fn buf_read() {
+    let input = common::sherlock();
+    let buffer = compress(input.as_bytes());
+
+    let reader = ArithmeticReader::new(ByteModel::new(), BufReader::new(buffer.as_slice()));
+    let lines: Vec<String> = reader.lines().map(Result::unwrap).collect();
+
+    assert_eq!(lines, input.lines().collect::<Vec<_>>());
+}
+
+#[test]
+fn compatible_with_decoder() {
+    let input = b"the quick brown fox jumps over the lazy dog";
+    let buffer = compress(input);
+
+    let mut decoder = Decoder::new(
+        ByteModel::new(),
+        BitReader::endian(buffer.as_slice(), BigEndian),
+    );
+    assert_eq!(decoder.decode_to_vec().unwrap(), input);
+}
+
+#[test]
+fn finish_on_drop() {
+    let input = b"the quick brown fox jumps over the lazy dog";
+
+    let mut buffer = Vec::new();
+    {
+        let mut writer = ArithmeticWriter::new(ByteModel::new(), &mut buffer);
+        writer.write_all(input).unwrap();
+    }
+
+    assert_eq!(buffer, compress(input));
+}
+
+#[test]
+fn corrupt_input() {
+    let buffer = [0xFF; 16];
+
+    let mut reader = ArithmeticReader::new(ByteModel::new(), buffer.as_slice());
+    let mut output = Vec::new();
+    let result = reader.read_to_end(&mut output);
+
+    // the input is either rejected, or decodes to some garbage
+    if let Err(e) = result {
+        assert_eq!(e.kind(), std::io::ErrorKind::InvalidData);
+    }
+}
+
+#[test]
+fn partial_write() {
+    let mut writer = ArithmeticWriter::new(ByteModel::ascii(), Vec::new());
+
+    // the bytes before the rejected byte are still written
+    assert_eq!(writer.write(b"ab\xFFcd").unwrap(), 2);
+    let e = writer.write(b"\xFFcd").unwrap_err();
+    assert_eq!(e.kind(), io::ErrorKind::InvalidInput);
+
+    writer.write_all(b"cd").unwrap();
+    let buffer = writer.finish().unwrap();
+
+    let mut output = Vec::new();
+    ArithmeticReader::new(ByteModel::ascii(), buffer.as_slice())
+        .read_to_end(&mut output)
+        .unwrap();
+    assert_eq!(output, b"abcd");
+}
+
+/// A reader which fails once its input runs out
+struct FailingReader<'a> {
+    input: &'a [u8],
+}
+
+impl Read for FailingReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.input.is_empty() {
+            return Err(io::Error::other("connection reset"));
+        }
+        self.input.read(buf)
+    }
+}
+
+#[test]
+fn error_is_terminal() {
+    let input = common::sherlock();
+    let input = &input.as_bytes()[..50_000];
+    let buffer = compress(input);
+
+    let reader = FailingReader {
+        input: &buffer[..buffer.len() / 2],
+    };
+    let mut reader = ArithmeticReader::new(ByteModel::new(), reader);
+
+    // the bytes decoded before the error are returned first
+    let mut output = Vec::new();
+    let e = reader.read_to_end(&mut output).unwrap_err();
+    assert_eq!(e.kind(), io::ErrorKind::Other);
+    assert!(!output.is_empty());
+    assert!(input.starts_with(&output));
+
+    // and decoding doesn't resume afterwards
+    for _ in 0..3 {
+        let e = reader.read(&mut [0; 16]).unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::Other);
+    }
+}
@@ -0,0 +1,91 @@
+use arithmetic_coding::Model;
+use fenwick_model::{Adaptation, order_k, simple};
+use test_case::test_case;
+
+mod common;
+
+fn sherlock() -> Vec<u8> {
+    let mut input = common::sherlock().into_bytes();
+    input.truncate(50_000);
+    input
+}
+
+fn model(order: usize) -> order_k::Builder<u8, u64> {
+    order_k::FenwickModel::builder(256, order, 1 << 20).adaptation(Adaptation::new().increment(32))
+}
+
+#[test_case(0; "order 0")]
+#[test_case(1; "order 1")]
+#[test_case(2; "order 2")]
+#[test_case(3; "order 3")]
+#[test_case(4; "order 4")]
+#[test_case(6; "order 6")]
+fn order(order: usize) {
+    common::round_trip(model(order).build(), &sherlock());
+}
+
+#[test]
+fn longer_contexts_compress_better() {
+    let input = sherlock();
+    let sizes: Vec<usize> = (0..=2)
+        .map(|order| common::round_trip(model(order).max_contexts(1 << 14).build(), &input))
+        .collect();
+    assert!(sizes.is_sorted_by(|a, b| a > b), "{sizes:?}");
+}
+
+/// With no longer contexts, the model is the same as the simple model.
+#[test]
+fn order_0_matches_simple() {
+    let input = sherlock();
+    let simple = simple::FenwickModel::<u8>::builder(256, 1 << 20)
+        .adaptation(Adaptation::new().increment(32))
+        .build();
+    assert_eq!(
+        common::encode(model(0).build(), &input),
+        common::encode(simple, &input)
+    );
+}
+
+/// A table with a single slot evicts a context on almost every symbol, but
+/// the encoder and decoder still agree.
+#[test]
+fn tiny_table() {
+    common::round_trip(model(3).max_contexts(1).confidence(1).build(), &sherlock());
+}
+
+#[test]
+fn falls_back_to_shorter_contexts() {
+    let mut model = model(2).confidence(2).build();
+    assert_eq!(model.current_order(), 0);
+
+    // neither "a" nor "ab" has been seen before
+    for &symbol in b"ab" {
+        model.update(Some(&symbol));
+        assert_eq!(model.current_order(), 0);
+    }
+    // "b" has been seen once, "ab" once
+    for &symbol in b"ab" {
+        model.update(Some(&symbol));
+    }
+    assert_eq!(model.current_order(), 0);
+
+    // both have been seen twice
+    for &symbol in b"ab" {
+        model.update(Some(&symbol));
+    }
+    assert_eq!(model.current_order(), 2);
+
+    // neither "c" nor "bc" has been seen as a context before
+    model.update(Some(&b'c'));
+    assert_eq!(model.current_order(), 0);
+
+    // "ca" hasn't been seen, but "a" has been seen three times
+    model.update(Some(&b'a'));
+    assert_eq!(model.current_order(), 1);
+}
+
+#[test]
+fn symbol_outside_alphabet() {
+    let model = order_k::FenwickModel::<u8>::builder(128, 2, 1 << 20).build();
+    assert!(model.probability(Some(&200)).is_err());
+}
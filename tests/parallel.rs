@@ -0,0 +1,84 @@
+use arithmetic_coding::{DecodeError, Decoder, bits::SliceReader, parallel};
+use fenwick_model::{context_switching, simple};
+use test_case::test_case;
+
+mod common;
+
+fn model() -> simple::FenwickModel {
+    simple::FenwickModel::builder(256, 1 << 20).build()
+}
+
+#[test_case(100; "small blocks")]
+#[test_case(1000; "many blocks")]
+#[test_case(usize::MAX; "one block")]
+fn round_trip(block_size: usize) {
+    let input = common::sherlock_symbols();
+
+    let buffer = parallel::encode(&input, block_size, model).unwrap();
+    let output = parallel::decode(&buffer, model).unwrap();
+
+    assert_eq!(input, output);
+}
+
+#[test]
+fn context_switching() {
+    let input = common::sherlock_symbols();
+    let model = || context_switching::FenwickModel::<usize>::with_symbols(256, 1 << 20);
+
+    let buffer = parallel::encode(&input, 4096, model).unwrap();
+    let output = parallel::decode(&buffer, model).unwrap();
+
+    assert_eq!(input, output);
+}
+
+#[test]
+fn empty() {
+    let buffer = parallel::encode(&[], 1000, model).unwrap();
+    assert!(parallel::blocks(&buffer).unwrap().is_empty());
+
+    let output = parallel::decode(&buffer, model).unwrap();
+    assert!(output.is_empty());
+}
+
+#[test]
+fn decode_single_block() {
+    let input = common::sherlock_symbols();
+
+    let buffer = parallel::encode(&input, 1000, model).unwrap();
+    let blocks = parallel::blocks(&buffer).unwrap();
+    assert_eq!(blocks.len(), input.len().div_ceil(1000));
+    assert_eq!(blocks[2].symbols, 1000);
+
+    let mut decoder =
+        Decoder::new(model(), SliceReader::new(blocks[2].bytes)).max_symbols(blocks[2].symbols);
+    let output = decoder.decode_to_vec().unwrap();
+
+    assert_eq!(output, input[2000..3000]);
+}
+
+#[test]
+fn corrupt_index() {
+    let input = common::sherlock_symbols();
+
+    let mut buffer = parallel::encode(&input, 1000, model).unwrap();
+    buffer[16..24].copy_from_slice(&u64::MAX.to_le_bytes());
+
+    assert!(parallel::blocks(&buffer).is_none());
+    assert!(matches!(
+        parallel::decode(&buffer, model),
+        Err(DecodeError::CorruptStream)
+    ));
+}
+
+#[test]
+fn too_many_symbols() {
+    let input = common::sherlock_symbols();
+
+    let mut buffer = parallel::encode(&input, 1000, model).unwrap();
+    buffer[8..16].copy_from_slice(&999_u64.to_le_bytes());
+
+    assert!(matches!(
+        parallel::decode(&buffer, model),
+        Err(DecodeError::TooManySymbols)
+    ));
+}
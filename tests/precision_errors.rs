@@ -0,0 +1,193 @@
+// unlike the asserts checked in `precision_checking.rs`, these errors are
+// returned in every build profile
+
+use std::{convert::Infallible, io::Cursor, ops::Range};
+
+use arithmetic_coding::{Decoder, Encoder, PrecisionError, decoder, encoder, precision_range};
+use arithmetic_coding_core::one_shot;
+use bitstream_io::{BigEndian, BitReader, BitWrite, BitWriter};
+
+#[derive(Copy, Clone)]
+struct Model {
+    max_denominator: u64,
+}
+
+impl one_shot::Model for Model {
+    type B = u64;
+    type Symbol = u64;
+    type ValueError = Infallible;
+
+    fn probability(&self, &value: &Self::Symbol) -> Result<Range<Self::B>, Self::ValueError> {
+        #[allow(clippy::range_plus_one)]
+        Ok(value..value + 1)
+    }
+
+    fn max_denominator(&self) -> Self::B {
+        self.max_denominator
+    }
+
+    fn symbol(&self, value: Self::B) -> Self::Symbol {
+        value
+    }
+}
+
+fn small() -> one_shot::Wrapper<Model> {
+    one_shot::Wrapper::new(Model { max_denominator: 2 })
+}
+
+// 31 bits
+fn big() -> one_shot::Wrapper<Model> {
+    one_shot::Wrapper::new(Model {
+        max_denominator: u64::from(u32::MAX) / 2,
+    })
+}
+
+// 62 bits, too big for any precision
+fn huge() -> one_shot::Wrapper<Model> {
+    one_shot::Wrapper::new(Model {
+        max_denominator: u64::MAX / 4,
+    })
+}
+
+#[test]
+fn range() {
+    assert_eq!(precision_range(&small()), Ok(4..=63));
+    assert_eq!(precision_range(&big()), Ok(33..=63));
+    assert_eq!(
+        precision_range(&huge()),
+        Err(PrecisionError::Precision {
+            needed: 64,
+            available: 63
+        })
+    );
+}
+
+#[test]
+fn encoder_try_new() {
+    let mut writer = BitWriter::endian(Vec::new(), BigEndian);
+    assert!(Encoder::try_new(big(), &mut writer).is_ok());
+    assert!(matches!(
+        Encoder::try_new(huge(), &mut writer),
+        Err(PrecisionError::Precision { .. })
+    ));
+}
+
+#[test]
+fn encoder_try_with_precision() {
+    let mut writer = BitWriter::endian(Vec::new(), BigEndian);
+
+    assert_eq!(
+        Encoder::try_with_precision(big(), &mut writer, 32).err(),
+        Some(PrecisionError::Precision {
+            needed: 33,
+            available: 32
+        })
+    );
+    assert_eq!(
+        Encoder::try_with_precision(big(), &mut writer, 64).err(),
+        Some(PrecisionError::BitStore {
+            needed: 65,
+            available: 64
+        })
+    );
+    assert!(Encoder::try_with_precision(big(), &mut writer, 33).is_ok());
+    assert!(Encoder::try_with_precision(big(), &mut writer, 63).is_ok());
+}
+
+#[test]
+fn wide_precision_round_trip() {
+    let symbol = u64::from(u32::MAX) / 2 - 1;
+
+    let mut output = Vec::new();
+    let mut writer = BitWriter::endian(&mut output, BigEndian);
+    let mut encoder = Encoder::try_with_precision(big(), &mut writer, 62).unwrap();
+    encoder.encode(Some(&symbol)).unwrap();
+    encoder.flush().unwrap();
+    writer.byte_align().unwrap();
+
+    let input = BitReader::endian(Cursor::new(&output), BigEndian);
+    let mut decoder = Decoder::try_with_precision(big(), input, 62).unwrap();
+    assert_eq!(decoder.decode().unwrap(), Some(symbol));
+}
+
+/// The default precision mustn't change, or streams encoded by earlier
+/// versions would no longer decode.
+#[test]
+fn default_precision_unchanged() {
+    let symbol = u64::from(u32::MAX) / 2 - 1;
+
+    let mut output = Vec::new();
+    let mut writer = BitWriter::endian(&mut output, BigEndian);
+    let mut encoder = Encoder::new(big(), &mut writer);
+    encoder.encode(Some(&symbol)).unwrap();
+    encoder.flush().unwrap();
+    writer.byte_align().unwrap();
+
+    let input = BitReader::endian(Cursor::new(&output), BigEndian);
+    let mut decoder = Decoder::with_precision(big(), input, 33);
+    assert_eq!(decoder.decode().unwrap(), Some(symbol));
+}
+
+#[test]
+fn encoder_try_with_state() {
+    let mut writer = BitWriter::endian(Vec::new(), BigEndian);
+    let state = encoder::State::new(32, &mut writer);
+    assert!(Encoder::try_with_state(state, big()).is_err());
+}
+
+#[test]
+fn encoder_try_chain() {
+    let mut writer = BitWriter::endian(Vec::new(), BigEndian);
+
+    let encoder = Encoder::with_precision(small(), &mut writer, 32);
+    assert!(encoder.try_chain(big()).is_err());
+
+    let encoder = Encoder::with_precision(small(), &mut writer, 33);
+    assert!(encoder.try_chain(big()).is_ok());
+}
+
+#[test]
+fn decoder_try_new() {
+    let input = BitReader::endian(Cursor::new(&[]), BigEndian);
+    assert!(Decoder::try_new(big(), input).is_ok());
+
+    let input = BitReader::endian(Cursor::new(&[]), BigEndian);
+    assert!(Decoder::try_new(huge(), input).is_err());
+}
+
+#[test]
+fn decoder_try_with_precision() {
+    let input = BitReader::endian(Cursor::new(&[]), BigEndian);
+    assert!(matches!(
+        Decoder::try_with_precision(big(), input, 32),
+        Err(PrecisionError::Precision {
+            needed: 33,
+            available: 32
+        })
+    ));
+}
+
+#[test]
+fn decoder_try_with_state() {
+    let state = decoder::State::new(32, BitReader::endian(Cursor::new(&[]), BigEndian));
+    assert!(Decoder::try_with_state(state, big()).is_err());
+}
+
+#[test]
+fn decoder_try_chain() {
+    let decoder =
+        Decoder::with_precision(small(), BitReader::endian(Cursor::new(&[]), BigEndian), 32);
+    assert!(decoder.try_chain(big()).is_err());
+}
+
+#[test]
+fn error_message() {
+    let error = PrecisionError::Precision {
+        needed: 33,
+        available: 32,
+    };
+    assert_eq!(
+        error.to_string(),
+        "not enough bits of precision to prevent overflow/underflow (33 bits needed, 32 available)"
+    );
+}
@@ -0,0 +1,173 @@
+use arithmetic_coding::{
+    DecodeError, Decoder,
+    seekable::{self, Index, InvalidIndex},
+};
+use fenwick_model::context_switching::FenwickModel;
+use test_case::test_case;
+
+mod common;
+
+fn model() -> FenwickModel {
+    FenwickModel::with_symbols(256, 1 << 20)
+}
+
+fn decode_rest(decoder: &mut seekable::Decoder<FenwickModel>) -> Vec<usize> {
+    let mut output = Vec::new();
+    while let Some(symbol) = decoder.decode().unwrap() {
+        output.push(symbol);
+    }
+    output
+}
+
+#[test_case(1000; "interval")]
+#[test_case(1; "every symbol")]
+#[test_case(u64::MAX; "single segment")]
+fn round_trip(interval: u64) {
+    let input = common::sherlock_symbols();
+    let input = &input[..20_000];
+
+    let (buffer, index) = seekable::Encoder::new(model())
+        .interval(interval)
+        .encode_all(input.iter().copied())
+        .unwrap();
+    assert_eq!(index.symbols, input.len() as u64);
+
+    let mut decoder = seekable::Decoder::new(model(), &buffer, &index);
+    assert_eq!(decode_rest(&mut decoder), input);
+    assert_eq!(decoder.position(), input.len() as u64);
+    assert_eq!(decoder.decode().unwrap(), None);
+}
+
+#[test]
+fn seek() {
+    let input = common::sherlock_symbols();
+    let input = &input[..20_000];
+
+    let (buffer, index) = seekable::Encoder::new(model())
+        .interval(1000)
+        .encode_all(input.iter().copied())
+        .unwrap();
+    assert_eq!(index.sync_points.len(), 20);
+
+    let mut decoder = seekable::Decoder::new(model(), &buffer, &index);
+    for position in [15_500, 0, 999, 1000, 1001, 19_999, 20_000, 7_777] {
+        decoder.seek(position).unwrap();
+        assert_eq!(decoder.position(), position);
+
+        let start = usize::try_from(position).unwrap();
+        let end = (start + 2000).min(input.len());
+        for expected in &input[start..end] {
+            assert_eq!(decoder.decode().unwrap().as_ref(), Some(expected));
+        }
+    }
+
+    decoder.seek(30_000).unwrap();
+    assert_eq!(decoder.position(), input.len() as u64);
+    assert_eq!(decoder.decode().unwrap(), None);
+}
+
+#[test]
+fn records() {
+    let input = common::sherlock_symbols();
+    let lines: Vec<&[usize]> = input
+        .split_inclusive(|&c| c == usize::from(b'\n'))
+        .take(500)
+        .collect();
+
+    let mut encoder = seekable::Encoder::new(model());
+    for line in &lines {
+        encoder.sync();
+        for symbol in *line {
+            encoder.encode(symbol).unwrap();
+        }
+    }
+    let (buffer, index) = encoder.finish().unwrap();
+    assert_eq!(index.sync_points.len(), lines.len());
+
+    // decode a single record, without decoding anything before it
+    for i in [0, 1, 100, lines.len() / 2, lines.len() - 1] {
+        let sync_point = &index.sync_points[i];
+        let mut decoder = Decoder::from_sync_point(model(), &buffer, sync_point);
+        assert_eq!(decoder.decode_to_vec().unwrap(), lines[i]);
+    }
+}
+
+#[test]
+fn find() {
+    let input = common::sherlock_symbols();
+    let (_, index) = seekable::Encoder::new(model())
+        .interval(100)
+        .encode_all(input[..1000].iter().copied())
+        .unwrap();
+
+    assert_eq!(index.find(0).unwrap().symbol_index, 0);
+    assert_eq!(index.find(99).unwrap().symbol_index, 0);
+    assert_eq!(index.find(100).unwrap().symbol_index, 100);
+    assert_eq!(index.find(555).unwrap().symbol_index, 500);
+}
+
+#[test]
+fn index_bytes() {
+    let input = common::sherlock_symbols();
+    let (buffer, index) = seekable::Encoder::new(model())
+        .interval(1000)
+        .encode_all(input[..20_000].iter().copied())
+        .unwrap();
+
+    let bytes = index.to_bytes();
+    assert_eq!(bytes.len(), 8 + 16 * 20);
+    let restored = Index::from_bytes(&bytes).unwrap();
+    assert_eq!(restored, index);
+
+    let mut decoder = seekable::Decoder::new(model(), &buffer, &restored);
+    decoder.seek(12_345).unwrap();
+    assert_eq!(decode_rest(&mut decoder), &input[12_345..20_000]);
+
+    assert_eq!(Index::from_bytes(&bytes[..7]), Err(InvalidIndex));
+    assert_eq!(
+        Index::from_bytes(&bytes[..bytes.len() - 1]),
+        Err(InvalidIndex)
+    );
+
+    // sync points out of order
+    let mut swapped = index;
+    swapped.sync_points.swap(3, 4);
+    assert_eq!(Index::from_bytes(&swapped.to_bytes()), Err(InvalidIndex));
+}
+
+#[test]
+fn segment_symbol_limit() {
+    let input = common::sherlock_symbols();
+    let (buffer, mut index) = seekable::Encoder::new(model())
+        .interval(1000)
+        .encode_all(input[..5000].iter().copied())
+        .unwrap();
+
+    // the index records fewer symbols than the last segment holds
+    index.symbols -= 1;
+
+    let mut decoder = seekable::Decoder::new(model(), &buffer, &index);
+    for expected in &input[..4999] {
+        assert_eq!(decoder.decode().unwrap().as_ref(), Some(expected));
+    }
+    assert!(matches!(decoder.decode(), Err(DecodeError::TooManySymbols)));
+}
+
+#[test]
+fn overhead() {
+    let input = common::sherlock_symbols();
+
+    let plain = common::encode(model(), &input);
+
+    let (single, _) = seekable::Encoder::new(model())
+        .encode_all(input.iter().copied())
+        .unwrap();
+    let (segmented, _) = seekable::Encoder::new(model())
+        .interval(10_000)
+        .encode_all(input.iter().copied())
+        .unwrap();
+
+    // a single segment is just a plain stream
+    assert_eq!(single, plain);
+    assert!(plain.len() < segmented.len());
+}
@@ -0,0 +1,86 @@
+use std::io::Cursor;
+
+use arithmetic_coding::{Decoder, Encoder, Termination};
+use bitstream_io::{BigEndian, BitRead, BitReader, BitWrite, BitWriter, BitsWritten};
+use fenwick_model::simple::FenwickModel;
+use test_case::test_case;
+
+const MARKER: u16 = 0xBEEF;
+
+fn model() -> FenwickModel {
+    FenwickModel::builder(256, 1 << 20).build()
+}
+
+fn input(len: usize) -> Vec<usize> {
+    b"the quick brown fox jumps over the lazy dog"
+        .iter()
+        .copied()
+        .map(usize::from)
+        .cycle()
+        .take(len)
+        .collect()
+}
+
+fn stream_bits(input: &[usize], termination: Termination) -> u64 {
+    let mut counter = BitsWritten::<u64>::new();
+    Encoder::new(model(), &mut counter)
+        .termination(termination)
+        .encode_all(input.iter().copied())
+        .unwrap();
+    counter.written()
+}
+
+#[test_case(Termination::Standard; "standard")]
+#[test_case(Termination::Minimal; "minimal")]
+fn data_after_stream(termination: Termination) {
+    for len in 0..50 {
+        let input = input(len);
+
+        let mut bitwriter = BitWriter::endian(Vec::new(), BigEndian);
+        bitwriter.write_bit(true).unwrap();
+        Encoder::new(model(), &mut bitwriter)
+            .termination(termination)
+            .encode_all(input.iter().copied())
+            .unwrap();
+        bitwriter.write::<16, u16>(MARKER).unwrap();
+        bitwriter.byte_align().unwrap();
+        let buffer = bitwriter.into_writer();
+
+        let mut bitreader = BitReader::endian(Cursor::new(buffer), BigEndian);
+        assert!(bitreader.read_bit().unwrap());
+
+        let mut decoder = Decoder::new(model(), bitreader).termination(termination);
+        let output = decoder.decode_to_vec().unwrap();
+        assert_eq!(output, input);
+
+        let (mut bitreader, bits) = decoder.finish().unwrap();
+        assert_eq!(bits, stream_bits(&input, termination));
+        assert_eq!(bitreader.read::<16, u16>().unwrap(), MARKER);
+    }
+}
+
+#[test]
+fn minimal_termination_is_no_longer_than_standard() {
+    for len in 0..50 {
+        let input = input(len);
+        assert!(
+            stream_bits(&input, Termination::Minimal) <= stream_bits(&input, Termination::Standard)
+        );
+    }
+}
+
+#[test]
+fn finish_detects_truncation() {
+    let mut bitwriter = BitWriter::endian(Vec::new(), BigEndian);
+    Encoder::new(model(), &mut bitwriter)
+        .encode_all(input(20))
+        .unwrap();
+    bitwriter.byte_align().unwrap();
+    let mut buffer = bitwriter.into_writer();
+    buffer.truncate(buffer.len() - 2);
+
+    let mut decoder =
+        Decoder::new(model(), BitReader::endian(Cursor::new(buffer), BigEndian)).max_symbols(100);
+    let _ = decoder.decode_to_vec();
+    assert!(decoder.finish().is_err());
+}
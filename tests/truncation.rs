@@ -0,0 +1,93 @@
+use arithmetic_coding::{DecodeError, Decoder, Encoder};
+use bitstream_io::{BigEndian, BitReader, BitWrite, BitWriter};
+use fenwick_model::simple::FenwickModel;
+
+const INPUT: &[u8] = b"the quick brown fox jumps over the lazy dog. \
+the quick brown fox jumps over the lazy dog.";
+
+fn model() -> FenwickModel {
+    FenwickModel::builder(256, 1 << 20).build()
+}
+
+fn encode(input: &[u8]) -> Vec<u8> {
+    let mut bitwriter = BitWriter::endian(Vec::new(), BigEndian);
+    Encoder::new(model(), &mut bitwriter)
+        .encode_all(input.iter().copied().map(usize::from))
+        .unwrap();
+    bitwriter.byte_align().unwrap();
+    bitwriter.into_writer()
+}
+
+fn decoder(buffer: &[u8]) -> Decoder<FenwickModel, BitReader<&[u8], BigEndian>> {
+    Decoder::new(model(), BitReader::endian(buffer, BigEndian))
+}
+
+fn expected() -> Vec<usize> {
+    INPUT.iter().copied().map(usize::from).collect()
+}
+
+#[test]
+fn complete_stream_decodes_in_strict_mode() {
+    let buffer = encode(INPUT);
+    let output = decoder(&buffer).strict().decode_to_vec().unwrap();
+    assert_eq!(output, expected());
+}
+
+#[test]
+fn empty_stream_decodes_in_strict_mode() {
+    let buffer = encode(&[]);
+    let output = decoder(&buffer).strict().decode_to_vec().unwrap();
+    assert!(output.is_empty());
+}
+
+#[test]
+fn truncated_stream_is_detected() {
+    let buffer = encode(INPUT);
+    let truncated = &buffer[..buffer.len() / 2];
+
+    let error = decoder(truncated).strict().decode_to_vec().unwrap_err();
+    assert!(matches!(error.error, DecodeError::Truncated));
+
+    // the safely decoded symbols are a prefix of the input
+    assert!(!error.symbols.is_empty());
+    assert!(expected().starts_with(&error.symbols));
+}
+
+#[test]
+fn iterator_stops_after_error() {
+    let buffer = encode(INPUT);
+    let truncated = &buffer[..buffer.len() / 2];
+
+    let mut decoder = decoder(truncated).strict();
+    let mut iter = decoder.decode_all();
+    assert!(iter.by_ref().any(|result| result.is_err()));
+    assert!(iter.next().is_none());
+}
+
+#[test]
+fn symbol_limit() {
+    let buffer = encode(INPUT);
+
+    let output = decoder(&buffer)
+        .max_symbols(INPUT.len())
+        .decode_to_vec()
+        .unwrap();
+    assert_eq!(output, expected());
+
+    let error = decoder(&buffer)
+        .max_symbols(10)
+        .decode_to_vec()
+        .unwrap_err();
+    assert!(matches!(error.error, DecodeError::TooManySymbols));
+    assert_eq!(error.symbols, expected()[..10]);
+}
+
+#[test]
+fn garbage_is_bounded_by_symbol_limit() {
+    let garbage = [0xA5; 4];
+
+    let result = decoder(&garbage).max_symbols(1000).decode_to_vec();
+    if let Err(error) = result {
+        assert!(error.symbols.len() <= 1000);
+    }
+}
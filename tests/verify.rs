@@ -0,0 +1,360 @@
+use std::{convert::Infallible, ops::Range};
+
+use arithmetic_coding::{
+    Model, fixed_length, max_length,
+    verify::{Coverage, Verifier, ViolationKind},
+};
+use fenwick_model::{Adaptation, context_switching, order_k, simple, windowed};
+
+mod common;
+
+/// Bytes, with EOF at 256. The faults are switched on by the
+/// constructors below.
+#[derive(Debug, Clone, Default)]
+struct Bytes {
+    /// Decode this value to the wrong symbol
+    misdecode: Option<u32>,
+
+    /// Give this symbol an empty range
+    empty: Option<u8>,
+
+    /// Grow the denominator with every update
+    growing: bool,
+
+    /// Claim that the denominator is a power of two
+    denominator_log2: Option<u32>,
+
+    /// Return the wrong range for this value from `decode_symbol`
+    bad_decode: Option<u32>,
+
+    updates: u32,
+}
+
+impl Model for Bytes {
+    type B = u32;
+    type Symbol = u8;
+    type ValueError = Infallible;
+
+    fn probability(&self, symbol: Option<&u8>) -> Result<Range<u32>, Infallible> {
+        Ok(match symbol {
+            Some(&s) if Some(s) == self.empty => u32::from(s)..u32::from(s),
+            Some(&s) => u32::from(s)..u32::from(s) + 1,
+            None => 256..self.denominator(),
+        })
+    }
+
+    fn symbol(&self, value: u32) -> Option<u8> {
+        if Some(value) == self.misdecode {
+            return Some(0);
+        }
+        u8::try_from(value).ok()
+    }
+
+    fn max_denominator(&self) -> u32 {
+        300
+    }
+
+    fn denominator(&self) -> u32 {
+        if self.growing {
+            257 + self.updates * 10
+        } else {
+            257
+        }
+    }
+
+    fn denominator_log2(&self) -> Option<u32> {
+        self.denominator_log2
+    }
+
+    fn decode_symbol(&self, value: u32) -> Result<(Option<u8>, Range<u32>), Infallible> {
+        let symbol = self.symbol(value);
+        let range = self.probability(symbol.as_ref())?;
+        if Some(value) == self.bad_decode {
+            return Ok((symbol, range.start..range.end + 1));
+        }
+        Ok((symbol, range))
+    }
+
+    fn update(&mut self, _symbol: Option<&u8>) {
+        self.updates += 1;
+    }
+}
+
+#[test]
+fn fenwick_models_pass() {
+    let input: Vec<usize> = common::sherlock_symbols().into_iter().take(500).collect();
+    let verifier = Verifier::new().coverage(Coverage::Sample(100));
+
+    let report = verifier.model(
+        simple::FenwickModel::<usize>::builder(256, 1 << 20).build(),
+        input.clone(),
+    );
+    assert!(report.is_ok(), "{report}");
+    assert_eq!(report.steps, input.len() + 1);
+
+    let report = verifier.model(
+        context_switching::FenwickModel::<usize>::with_symbols(256, 1 << 20),
+        input.clone(),
+    );
+    assert!(report.is_ok(), "{report}");
+
+    let report = verifier.model(
+        windowed::FenwickModel::<usize>::builder(256, 1000).build(),
+        input.clone(),
+    );
+    assert!(report.is_ok(), "{report}");
+
+    let report = verifier.model(
+        order_k::FenwickModel::<usize>::builder(256, 3, 1 << 20)
+            .adaptation(Adaptation::new().increment(32))
+            .build(),
+        input,
+    );
+    assert!(report.is_ok(), "{report}");
+}
+
+#[test]
+fn exhaustive() {
+    let report = Verifier::new().model(Bytes::default(), *b"hello world");
+    assert!(report.is_ok(), "{report}");
+    assert_eq!(report.steps, 12);
+}
+
+#[test]
+fn not_inverse() {
+    let model = Bytes {
+        misdecode: Some(100),
+        ..Bytes::default()
+    };
+
+    let report = Verifier::new().model(model.clone(), []);
+    assert_eq!(report.violations.len(), 1);
+    assert_eq!(
+        report.violations[0].kind,
+        ViolationKind::NotInverse {
+            value: 100,
+            symbol: Some(0),
+            range: 0..1,
+        }
+    );
+
+    // the first value of every range is always checked
+    let report = Verifier::new()
+        .coverage(Coverage::Sample(0))
+        .model(model, []);
+    assert!(!report.is_ok());
+}
+
+#[test]
+fn empty_range() {
+    let model = Bytes {
+        empty: Some(b'l'),
+        ..Bytes::default()
+    };
+
+    let report = Verifier::new().model(model, *b"hello");
+    assert!(!report.is_ok());
+    assert!(report.violations.iter().any(|violation| {
+        violation.step == 2
+            && violation.kind
+                == ViolationKind::EmptyRange {
+                    symbol: Some(b'l'),
+                    range: 108..108,
+                }
+    }));
+}
+
+#[test]
+fn denominator_grows_too_large() {
+    let model = Bytes {
+        growing: true,
+        ..Bytes::default()
+    };
+
+    let report = Verifier::new().model(model, *b"hello world");
+    let first = &report.violations[0];
+    assert_eq!(first.step, 5);
+    assert_eq!(
+        first.kind,
+        ViolationKind::DenominatorTooLarge {
+            denominator: 307,
+            max_denominator: 300
+        }
+    );
+}
+
+#[test]
+fn wrong_denominator_log2() {
+    let model = Bytes {
+        denominator_log2: Some(8),
+        ..Bytes::default()
+    };
+
+    let report = Verifier::new().model(model, *b"hi");
+    assert_eq!(report.violations.len(), 3);
+    assert_eq!(
+        report.violations[0].kind,
+        ViolationKind::WrongDenominatorLog2 {
+            denominator: 257,
+            log2: 8
+        }
+    );
+}
+
+#[test]
+fn inconsistent_decode_symbol() {
+    let model = Bytes {
+        bad_decode: Some(b'h'.into()),
+        ..Bytes::default()
+    };
+
+    let report = Verifier::new().model(model, *b"hi");
+    assert_eq!(report.violations.len(), 3);
+    assert_eq!(
+        report.violations[0].kind,
+        ViolationKind::InconsistentDecodeSymbol {
+            value: 104,
+            symbol: Some(b'h'),
+            range: 104..106,
+        }
+    );
+}
+
+#[test]
+fn overlap() {
+    #[derive(Debug)]
+    struct Overlapping;
+
+    impl Model for Overlapping {
+        type B = u32;
+        type Symbol = u8;
+        type ValueError = Infallible;
+
+        fn probability(&self, symbol: Option<&u8>) -> Result<Range<u32>, Infallible> {
+            Ok(match symbol {
+                Some(0) => 0..2,
+                Some(&s) => u32::from(s)..u32::from(s) + 1,
+                None => 4..5,
+            })
+        }
+
+        fn symbol(&self, value: u32) -> Option<u8> {
+            (value < 4).then(|| u8::try_from(value).unwrap())
+        }
+
+        fn max_denominator(&self) -> u32 {
+            5
+        }
+    }
+
+    let report = Verifier::new().model(Overlapping, []);
+    assert_eq!(
+        report.violations[0].kind,
+        ViolationKind::Overlap {
+            symbol: Some(1),
+            range: 1..2,
+            other: 0..2,
+        }
+    );
+}
+
+#[test]
+fn max_violations() {
+    let model = Bytes {
+        misdecode: Some(100),
+        ..Bytes::default()
+    };
+
+    let report = Verifier::new()
+        .max_violations(3)
+        .model(model, *b"hello world");
+    assert_eq!(report.violations.len(), 3);
+    assert!(report.truncated);
+    assert!(report.steps < 12);
+}
+
+#[test]
+fn report() {
+    let model = Bytes {
+        misdecode: Some(100),
+        ..Bytes::default()
+    };
+
+    let report = Verifier::new().model(model, []);
+    assert_eq!(
+        report.to_string(),
+        "1 violations in 1 steps\n  step 0: the value 100 decodes to Some(0), but its range \
+         (0..1) doesn't contain the value"
+    );
+}
+
+#[derive(Debug)]
+struct FixedBytes(usize);
+
+impl fixed_length::Model for FixedBytes {
+    type B = u32;
+    type Symbol = u8;
+    type ValueError = Infallible;
+
+    fn probability(&self, symbol: &u8) -> Result<Range<u32>, Infallible> {
+        Ok(u32::from(*symbol)..u32::from(*symbol) + 1)
+    }
+
+    fn symbol(&self, value: u32) -> u8 {
+        u8::try_from(value).unwrap()
+    }
+
+    fn max_denominator(&self) -> u32 {
+        256
+    }
+
+    fn length(&self) -> usize {
+        self.0
+    }
+}
+
+#[test]
+fn fixed_length() {
+    let report = Verifier::new().fixed_length(FixedBytes(5), *b"hello world");
+    assert!(report.is_ok(), "{report}");
+    assert_eq!(report.steps, 6);
+}
+
+#[derive(Debug)]
+struct MaxBytes;
+
+impl max_length::Model for MaxBytes {
+    type B = u32;
+    type Symbol = u8;
+    type ValueError = Infallible;
+
+    fn probability(&self, symbol: Option<&u8>) -> Result<Range<u32>, Infallible> {
+        // EOF has no range of its own
+        Ok(symbol.map_or(0..1, |&s| u32::from(s)..u32::from(s) + 1))
+    }
+
+    fn symbol(&self, value: u32) -> Option<u8> {
+        u8::try_from(value).ok()
+    }
+
+    fn max_denominator(&self) -> u32 {
+        256
+    }
+
+    fn max_length(&self) -> usize {
+        8
+    }
+}
+
+#[test]
+fn max_length() {
+    let report = Verifier::new().max_length(MaxBytes, *b"hello world");
+    assert_eq!(report.steps, 9);
+    assert!(report.violations.iter().all(|violation| violation.kind
+        == ViolationKind::NotInverse {
+            value: 0,
+            symbol: Some(0),
+            range: 0..1
+        }));
+    assert_eq!(report.violations.len(), 8);
+}
@@ -0,0 +1,97 @@
+use arithmetic_coding::Model;
+use fenwick_model::{simple, windowed};
+use test_case::test_case;
+
+mod common;
+
+#[test_case(1, 1; "single symbol window")]
+#[test_case(1000, 1; "small window")]
+#[test_case(1 << 16, 1; "large window")]
+#[test_case(1000, 16; "large increment")]
+fn fenwick_windowed(window: usize, increment: u64) {
+    let model = windowed::FenwickModel::builder(256, window)
+        .increment(increment)
+        .build();
+    common::round_trip(model, &common::sherlock_symbols()[..100_000]);
+}
+
+#[test]
+fn forgets_old_symbols() {
+    let mut model = windowed::FenwickModel::<usize>::builder(3, 4)
+        .increment(2)
+        .build();
+    assert_eq!(model.max_denominator(), 12);
+
+    for _ in 0..4 {
+        model.update(Some(&0));
+    }
+    assert_eq!(model.probability(Some(&0)).unwrap(), 1..10);
+    assert_eq!(model.denominator(), 12);
+
+    for _ in 0..4 {
+        model.update(Some(&2));
+    }
+    assert_eq!(model.probability(Some(&0)).unwrap(), 1..2);
+    assert_eq!(model.probability(Some(&2)).unwrap(), 3..12);
+    assert_eq!(model.denominator(), 12);
+}
+
+#[test]
+fn forgets_eof() {
+    let mut model = windowed::FenwickModel::<usize>::builder(3, 2).build();
+
+    model.update(None);
+    model.update(None);
+    assert_eq!(model.probability(None).unwrap(), 0..3);
+
+    model.update(Some(&1));
+    model.update(Some(&1));
+    assert_eq!(model.probability(None).unwrap(), 0..1);
+    assert_eq!(model.probability(Some(&1)).unwrap(), 2..5);
+}
+
+/// The ring buffer only grows as symbols are seen, so a window much larger
+/// than the input doesn't allocate up front
+#[test]
+fn huge_window() {
+    let mut model = windowed::FenwickModel::<usize>::builder(3, 1 << 31).build();
+    assert_eq!(model.max_denominator(), (1 << 31) + 4);
+
+    for _ in 0..1000 {
+        model.update(Some(&1));
+    }
+    assert_eq!(model.probability(Some(&1)).unwrap(), 2..1003);
+    assert_eq!(model.denominator(), 1004);
+}
+
+/// A window tracks a distribution which changes partway through the input,
+/// which a model that counts every symbol can't.
+#[test]
+fn tracks_changing_distribution() {
+    let input: Vec<usize> = (0..100_000).map(|i| i / 50_000 + (i % 3)).collect();
+
+    let everything = common::round_trip(
+        simple::FenwickModel::<usize>::builder(4, 1 << 20).build(),
+        &input,
+    );
+    let window = common::round_trip(
+        windowed::FenwickModel::<usize>::builder(4, 1000).build(),
+        &input,
+    );
+
+    assert!(window < everything, "{window} >= {everything}");
+}
+
+#[test]
+#[should_panic(expected = "window is too large for the BitStore")]
+fn window_too_large() {
+    let _ = windowed::FenwickModel::<usize, u32>::builder(4, usize::MAX).build();
+}
+
+#[test]
+#[should_panic(expected = "maximum denominator overflows the BitStore")]
+fn denominator_overflow() {
+    let _ = windowed::FenwickModel::<usize, u32>::builder(4, 1 << 16)
+        .increment(1 << 16)
+        .build();
+}